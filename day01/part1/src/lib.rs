@@ -0,0 +1,20 @@
+use std::str::FromStr;
+
+/// Parses a sonar sweep of whitespace-separated depth readings.
+pub fn parse_input<T: FromStr>(input: &str) -> Result<Vec<T>, T::Err> {
+    input.split_whitespace().map(|v| v.parse()).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn example_parse() {
+        assert_eq!(
+            parse_input::<u16>("199\n200\n208\n210\n"),
+            Ok(vec![199, 200, 208, 210])
+        );
+        assert!(parse_input::<u16>("199 deep").is_err());
+    }
+}
@@ -1,6 +1,8 @@
 use std::{fs::File, io::Read};
 
-fn get_depth_increases(list: &Vec<u16>) -> usize {
+use day01_part1::parse_input;
+
+fn get_depth_increases(list: &[u16]) -> usize {
     list.windows(2).filter(|res| res[0] < res[1]).count()
 }
 
@@ -11,10 +13,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         file.read_to_string(&mut contents)?;
     }
 
-    let values = contents
-        .split_whitespace()
-        .map(|v| v.parse())
-        .collect::<Result<_, _>>()?;
+    let values: Vec<u16> = parse_input(&contents)?;
 
     let depth_increases = get_depth_increases(&values);
 
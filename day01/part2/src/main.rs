@@ -1,6 +1,6 @@
 use std::{fs::File, io::Read};
 
-fn get_depth_increases(list: &Vec<u16>) -> usize {
+fn get_depth_increases(list: &[u16]) -> usize {
     list.windows(4).filter(|res| res[0] < res[3]).count()
 }
 
@@ -11,7 +11,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         file.read_to_string(&mut contents)?;
     }
 
-    let values = contents
+    let values: Vec<u16> = contents
         .split_whitespace()
         .map(|v| v.parse())
         .collect::<Result<_, _>>()?;
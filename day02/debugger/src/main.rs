@@ -0,0 +1,195 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, Read, Write},
+};
+
+use day02_part1::{
+    debugger::{Breakpoint, Debugger, ParseBreakpointError, Stop},
+    script::compile,
+    Model,
+};
+
+#[derive(Debug, Eq, PartialEq)]
+enum Command {
+    Step,
+    Back,
+    Run,
+    RunBack,
+    Break(Breakpoint),
+    Delete(usize),
+    Quit,
+}
+
+fn parse_command(line: &str) -> Result<Command, String> {
+    match line.split_once(' ') {
+        Some(("break", breakpoint)) => breakpoint
+            .trim()
+            .parse()
+            .map(Command::Break)
+            .map_err(|error: ParseBreakpointError| error.to_string()),
+        Some(("delete", index)) => index
+            .trim()
+            .parse()
+            .map(Command::Delete)
+            .map_err(|_| format!("`{}` is not a breakpoint number", index.trim())),
+        _ => match line {
+            "step" | "s" => Ok(Command::Step),
+            "back" | "b" => Ok(Command::Back),
+            "run" | "r" => Ok(Command::Run),
+            "rback" => Ok(Command::RunBack),
+            "quit" | "q" => Ok(Command::Quit),
+            _ => Err(format!(
+                "unknown command `{}`, expected step, back, run, rback, break, delete or quit",
+                line
+            )),
+        },
+    }
+}
+
+fn execute(debugger: &mut Debugger, command: Command) -> String {
+    match command {
+        Command::Step => match debugger.step() {
+            Ok(Some(report)) => report.to_string(),
+            Ok(None) => "At the end of the script".to_string(),
+            Err(error) => format!("Cannot step: {}", error),
+        },
+        Command::Back => match debugger.back() {
+            Some(report) => format!("undid {}", report),
+            None => "At the start of the script".to_string(),
+        },
+        Command::Run | Command::RunBack => {
+            let stop = if command == Command::Run {
+                debugger.run()
+            } else {
+                debugger.run_back()
+            };
+            let reason = match stop {
+                Stop::Breakpoint(index) => format!("breakpoint {}", index),
+                Stop::End => "end of script".to_string(),
+                Stop::Start => "start of script".to_string(),
+                Stop::Overflow(error) => format!("an overflow ({})", error),
+            };
+            format!(
+                "Stopped at {} before instruction {}: {:?}",
+                reason,
+                debugger.position(),
+                debugger.state()
+            )
+        }
+        Command::Break(breakpoint) => {
+            debugger.breakpoints.push(breakpoint);
+            format!(
+                "Breakpoint {}: {:?}",
+                debugger.breakpoints.len() - 1,
+                breakpoint
+            )
+        }
+        Command::Delete(index) if index < debugger.breakpoints.len() => {
+            format!("Deleted {:?}", debugger.breakpoints.remove(index))
+        }
+        Command::Delete(index) => format!("No breakpoint {}", index),
+        Command::Quit => String::new(),
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut arguments = std::env::args().skip(1);
+    let path = arguments
+        .next()
+        .unwrap_or_else(|| "./day02/part1/input.txt".to_string());
+    let model = match arguments.next() {
+        Some(model) => model.parse()?,
+        None => Model::Aim,
+    };
+
+    let mut contents = String::new();
+    {
+        let mut file = File::open(&path)?;
+        file.read_to_string(&mut contents)?;
+    }
+
+    let mut debugger = Debugger::new(model, compile(&contents)?);
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    loop {
+        print!("({}) ", debugger.position());
+        stdout.flush()?;
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            break;
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        match parse_command(line) {
+            Ok(Command::Quit) => break,
+            Ok(command) => println!("{}", execute(&mut debugger, command)),
+            Err(error) => println!("Error: {}", error),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use day02_part1::{
+        debugger::{Comparison, Field},
+        Instruction,
+    };
+
+    #[test]
+    fn example_parse_command() {
+        assert_eq!(parse_command("s"), Ok(Command::Step));
+        assert_eq!(
+            parse_command("break depth > 1000"),
+            Ok(Command::Break(Breakpoint::Condition(
+                Field::Depth,
+                Comparison::Greater,
+                1000
+            )))
+        );
+        assert_eq!(parse_command("delete 2"), Ok(Command::Delete(2)));
+        assert!(parse_command("jump").is_err());
+    }
+
+    #[test]
+    fn example_session() {
+        let mut debugger = Debugger::new(
+            Model::Aim,
+            vec![
+                Instruction::Forward(5),
+                Instruction::Down(5),
+                Instruction::Forward(8),
+            ],
+        );
+
+        execute(&mut debugger, parse_command("break index 2").unwrap());
+        assert!(execute(&mut debugger, Command::Run).starts_with("Stopped at breakpoint 0"));
+        assert_eq!(
+            execute(&mut debugger, Command::Step),
+            "#2 forward 8: x=5 y=0 depth=0 aim=5 heading=East -> x=13 y=0 depth=40 aim=5 heading=East"
+        );
+        assert!(execute(&mut debugger, Command::Back).starts_with("undid #2"));
+        assert_eq!(
+            execute(&mut debugger, Command::Delete(3)),
+            "No breakpoint 3"
+        );
+    }
+
+    #[test]
+    fn overflow_session() {
+        let mut debugger = Debugger::new(
+            Model::Direct,
+            vec![Instruction::Forward(i64::MAX), Instruction::Forward(1)],
+        );
+
+        assert!(execute(&mut debugger, Command::Run)
+            .starts_with("Stopped at an overflow (instruction 1 (forward 1) overflows) before"));
+    }
+}
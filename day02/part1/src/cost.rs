@@ -0,0 +1,187 @@
+use crate::{trajectory::Segment, Coordinate, Instruction, Model, OverflowError, Submarine};
+
+pub type Cost = i64;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct CostModel {
+    /// Cost per unit of each command, with turns costing `turn` each.
+    pub forward: Cost,
+    pub back: Cost,
+    pub down: Cost,
+    pub up: Cost,
+    pub turn: Cost,
+    pub strafe: Cost,
+    /// Extra cost per unit of horizontal movement for each unit of depth below the surface.
+    pub drag: Cost,
+    /// Extra cost for each `down` or `up` under the aim model.
+    pub aim_change: Cost,
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        CostModel {
+            forward: 1,
+            back: 1,
+            down: 1,
+            up: 1,
+            turn: 1,
+            strafe: 1,
+            drag: 0,
+            aim_change: 0,
+        }
+    }
+}
+
+/// The depth after each unit of `segment` summed over the units below the surface, or `None` if
+/// the sum is out of range.
+fn depth_below_surface(segment: &Segment) -> Option<Cost> {
+    let Some((first, last)) = segment.units_at_depth(1, Coordinate::MAX) else {
+        return Some(0);
+    };
+    // Depth changes steadily, so the sum is the number of units times the average depth.
+    let ends = segment.state(first).depth as i128 + segment.state(last).depth as i128;
+    let sum = ends.checked_mul((last - first + 1) as i128)? / 2;
+    Cost::try_from(sum).ok()
+}
+
+impl CostModel {
+    /// The cost of running `instruction` from `before`, or `None` if it overflows.
+    pub fn instruction_cost(
+        &self,
+        model: Model,
+        before: &Submarine,
+        instruction: &Instruction,
+    ) -> Option<Cost> {
+        let (rate, magnitude): (Cost, Coordinate) = match *instruction {
+            Instruction::Forward(distance) => (self.forward, distance),
+            Instruction::Back(distance) => (self.back, distance),
+            Instruction::Down(delta) => (self.down, delta),
+            Instruction::Up(delta) => (self.up, delta),
+            Instruction::Turn(_) => (self.turn, 1),
+            Instruction::Strafe(distance) => (self.strafe, distance),
+        };
+        let mut cost = rate.checked_mul(magnitude.checked_abs()?)?;
+
+        match instruction {
+            Instruction::Forward(_) | Instruction::Back(_) | Instruction::Strafe(_)
+                if self.drag != 0 =>
+            {
+                let segment = Segment::new(model, *before, *instruction)?;
+                cost = cost.checked_add(self.drag.checked_mul(depth_below_surface(&segment)?)?)?;
+            }
+            Instruction::Down(_) | Instruction::Up(_) if model == Model::Aim => {
+                cost = cost.checked_add(self.aim_change)?;
+            }
+            _ => {}
+        }
+
+        Some(cost)
+    }
+}
+
+/// Runs `instructions` like `calculate_position`, also returning their total cost.
+pub fn calculate_cost(
+    model: Model,
+    instructions: &mut dyn Iterator<Item = Instruction>,
+    costs: &CostModel,
+) -> Result<(Submarine, Cost), OverflowError> {
+    instructions.enumerate().try_fold(
+        (Submarine::default(), 0),
+        |(submarine, cost): (Submarine, Cost), (index, instruction)| {
+            let after = submarine.step(model, &instruction);
+            let cost = costs
+                .instruction_cost(model, &submarine, &instruction)
+                .and_then(|instruction_cost| cost.checked_add(instruction_cost));
+            match (after, cost) {
+                (Some(after), Some(cost)) => Ok((after, cost)),
+                _ => Err(OverflowError { index, instruction }),
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{example_instructions, Turn};
+
+    #[test]
+    fn example_default_cost() {
+        for model in [Model::Direct, Model::Aim] {
+            let (_, cost) = calculate_cost(
+                model,
+                &mut example_instructions().into_iter(),
+                &CostModel::default(),
+            )
+            .unwrap();
+
+            assert_eq!(cost, 31);
+        }
+    }
+
+    #[test]
+    fn example_drag_and_aim_changes() {
+        let costs = CostModel {
+            drag: 1,
+            aim_change: 10,
+            ..CostModel::default()
+        };
+
+        let (submarine, cost) = calculate_cost(
+            Model::Direct,
+            &mut example_instructions().into_iter(),
+            &costs,
+        )
+        .unwrap();
+        assert_eq!((submarine.x, submarine.depth), (15, 10));
+        assert_eq!(cost, 31 + 8 * 5 + 2 * 10);
+
+        let (submarine, cost) =
+            calculate_cost(Model::Aim, &mut example_instructions().into_iter(), &costs).unwrap();
+        assert_eq!((submarine.x, submarine.depth), (15, 60));
+        assert_eq!(
+            cost,
+            31 + (5 + 10 + 15 + 20 + 25 + 30 + 35 + 40) + (50 + 60) + 3 * 10
+        );
+    }
+
+    #[test]
+    fn per_command_rates() {
+        let costs = CostModel {
+            forward: 2,
+            turn: 7,
+            strafe: 3,
+            ..CostModel::default()
+        };
+        let input = vec![
+            Instruction::Forward(4),
+            Instruction::Turn(Turn::Left),
+            Instruction::Strafe(-2),
+        ];
+
+        let (_, cost) = calculate_cost(Model::Direct, &mut input.into_iter(), &costs).unwrap();
+
+        assert_eq!(cost, 8 + 7 + 6);
+    }
+
+    #[test]
+    fn drag_over_long_moves() {
+        let costs = CostModel {
+            drag: 1,
+            ..CostModel::default()
+        };
+        let input = vec![
+            Instruction::Up(1),
+            Instruction::Forward(3),
+            Instruction::Down(2),
+            Instruction::Forward(200_000_000),
+        ];
+
+        let (_, cost) = calculate_cost(Model::Aim, &mut input.clone().into_iter(), &costs).unwrap();
+        let below = (1 + 200_000_000 - 3) * (200_000_000 - 3) / 2;
+        assert_eq!(cost, 1 + 3 + 2 + 200_000_000 + below);
+
+        let (_, cost) = calculate_cost(Model::Direct, &mut input.into_iter(), &costs).unwrap();
+        assert_eq!(cost, 1 + 3 + 2 + 200_000_000 + 200_000_000);
+    }
+}
@@ -0,0 +1,336 @@
+use std::{fmt, str::FromStr};
+
+use crate::{Coordinate, Instruction, Model, OverflowError, Submarine};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Field {
+    X,
+    Y,
+    Depth,
+    Aim,
+}
+
+impl Field {
+    pub fn get(self, submarine: &Submarine) -> Coordinate {
+        match self {
+            Field::X => submarine.x,
+            Field::Y => submarine.y,
+            Field::Depth => submarine.depth,
+            Field::Aim => submarine.aim,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Comparison {
+    pub fn holds(self, left: Coordinate, right: Coordinate) -> bool {
+        match self {
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Breakpoint {
+    /// Stops after any step leaving the state satisfying the condition.
+    Condition(Field, Comparison, Coordinate),
+    /// Stops before executing the instruction at this index.
+    Index(usize),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseBreakpointError(pub String);
+
+impl fmt::Display for ParseBreakpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` is not a breakpoint, expected `index N` or `FIELD OP VALUE`",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseBreakpointError {}
+
+impl FromStr for Breakpoint {
+    type Err = ParseBreakpointError;
+
+    /// Parses `index 12` or a condition such as `depth > 1000` or `aim < 0`.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = || ParseBreakpointError(text.to_string());
+        match text.split_whitespace().collect::<Vec<_>>()[..] {
+            ["index", index] => index.parse().map(Breakpoint::Index).map_err(|_| error()),
+            [field, comparison, value] => {
+                let field = match field {
+                    "x" | "horizontal" => Field::X,
+                    "y" => Field::Y,
+                    "depth" => Field::Depth,
+                    "aim" => Field::Aim,
+                    _ => return Err(error()),
+                };
+                let comparison = match comparison {
+                    "<" => Comparison::Less,
+                    "<=" => Comparison::LessOrEqual,
+                    ">" => Comparison::Greater,
+                    ">=" => Comparison::GreaterOrEqual,
+                    "==" | "=" => Comparison::Equal,
+                    "!=" => Comparison::NotEqual,
+                    _ => return Err(error()),
+                };
+                let value = value.parse().map_err(|_| error())?;
+                Ok(Breakpoint::Condition(field, comparison, value))
+            }
+            _ => Err(error()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct StepReport {
+    pub index: usize,
+    pub instruction: Instruction,
+    pub before: Submarine,
+    pub after: Submarine,
+}
+
+fn describe(submarine: &Submarine) -> String {
+    format!(
+        "x={} y={} depth={} aim={} heading={:?}",
+        submarine.x, submarine.y, submarine.depth, submarine.aim, submarine.heading
+    )
+}
+
+impl fmt::Display for StepReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{} {}: {} -> {}",
+            self.index,
+            self.instruction,
+            describe(&self.before),
+            describe(&self.after)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Stop {
+    /// The breakpoint at this position in the breakpoint list was hit.
+    Breakpoint(usize),
+    End,
+    Start,
+    /// The next instruction would overflow, so it was not run.
+    Overflow(OverflowError),
+}
+
+pub struct Debugger {
+    model: Model,
+    instructions: Vec<Instruction>,
+    /// `states[i]` is the state before instruction `i`; the last entry is the current state.
+    states: Vec<Submarine>,
+    pub breakpoints: Vec<Breakpoint>,
+}
+
+impl Debugger {
+    pub fn new(model: Model, instructions: Vec<Instruction>) -> Self {
+        Debugger {
+            model,
+            instructions,
+            states: vec![Submarine::default()],
+            breakpoints: vec![],
+        }
+    }
+
+    /// The number of instructions executed so far, which is also the index of the next one.
+    pub fn position(&self) -> usize {
+        self.states.len() - 1
+    }
+
+    pub fn state(&self) -> Submarine {
+        *self.states.last().unwrap()
+    }
+
+    /// Runs the next instruction, returning `None` at the end of the script. An instruction that
+    /// overflows is not run, leaving the debugger where it was.
+    pub fn step(&mut self) -> Result<Option<StepReport>, OverflowError> {
+        let index = self.position();
+        let Some(&instruction) = self.instructions.get(index) else {
+            return Ok(None);
+        };
+        let before = self.state();
+        let after = before
+            .step(self.model, &instruction)
+            .ok_or(OverflowError { index, instruction })?;
+        self.states.push(after);
+        Ok(Some(StepReport {
+            index,
+            instruction,
+            before,
+            after,
+        }))
+    }
+
+    /// Undoes the most recent step, returning the report of the step that was undone.
+    pub fn back(&mut self) -> Option<StepReport> {
+        if self.position() == 0 {
+            return None;
+        }
+        let after = self.states.pop().unwrap();
+        let index = self.position();
+        Some(StepReport {
+            index,
+            instruction: self.instructions[index],
+            before: self.state(),
+            after,
+        })
+    }
+
+    fn hit(&self, after_step: bool) -> Option<usize> {
+        self.breakpoints
+            .iter()
+            .position(|breakpoint| match *breakpoint {
+                Breakpoint::Condition(field, comparison, value) => {
+                    after_step && comparison.holds(field.get(&self.state()), value)
+                }
+                Breakpoint::Index(index) => index == self.position(),
+            })
+    }
+
+    /// Steps forward until a breakpoint is hit or the script ends, always taking at least one
+    /// step.
+    pub fn run(&mut self) -> Stop {
+        loop {
+            match self.step() {
+                Ok(Some(_)) => {}
+                Ok(None) => return Stop::End,
+                Err(error) => return Stop::Overflow(error),
+            }
+            if let Some(breakpoint) = self.hit(true) {
+                return Stop::Breakpoint(breakpoint);
+            }
+        }
+    }
+
+    /// Steps backward until a breakpoint is hit or the start is reached, always undoing at least
+    /// one step.
+    pub fn run_back(&mut self) -> Stop {
+        while self.back().is_some() {
+            if let Some(breakpoint) = self.hit(self.position() > 0) {
+                return Stop::Breakpoint(breakpoint);
+            }
+        }
+        Stop::Start
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::example_instructions;
+
+    #[test]
+    fn parse_breakpoints() {
+        assert_eq!(
+            "depth > 1000".parse(),
+            Ok(Breakpoint::Condition(
+                Field::Depth,
+                Comparison::Greater,
+                1000
+            ))
+        );
+        assert_eq!(
+            "aim < 0".parse(),
+            Ok(Breakpoint::Condition(Field::Aim, Comparison::Less, 0))
+        );
+        assert_eq!("index 4".parse(), Ok(Breakpoint::Index(4)));
+        assert_eq!(
+            "speed > 3".parse::<Breakpoint>(),
+            Err(ParseBreakpointError("speed > 3".to_string()))
+        );
+    }
+
+    #[test]
+    fn step_and_back() {
+        let mut debugger = Debugger::new(Model::Aim, example_instructions());
+
+        debugger.step().unwrap();
+        debugger.step().unwrap();
+        let report = debugger.step().unwrap().unwrap();
+        assert_eq!(report.index, 2);
+        assert_eq!(report.before.depth, 0);
+        assert_eq!(report.after.depth, 40);
+        assert_eq!(
+            report.to_string(),
+            "#2 forward 8: x=5 y=0 depth=0 aim=5 heading=East -> x=13 y=0 depth=40 aim=5 heading=East"
+        );
+
+        let undone = debugger.back().unwrap();
+        assert_eq!(undone, report);
+        assert_eq!(debugger.position(), 2);
+        assert_eq!(debugger.state(), report.before);
+
+        assert_eq!(debugger.back().unwrap().index, 1);
+        assert_eq!(debugger.back().unwrap().index, 0);
+        assert_eq!(debugger.back(), None);
+    }
+
+    #[test]
+    fn run_to_breakpoints() {
+        let mut debugger = Debugger::new(Model::Aim, example_instructions());
+        debugger.breakpoints = vec!["depth > 30".parse().unwrap(), "index 5".parse().unwrap()];
+
+        assert_eq!(debugger.run(), Stop::Breakpoint(0));
+        assert_eq!(debugger.position(), 3);
+        assert_eq!(debugger.state().depth, 40);
+
+        debugger.breakpoints.remove(0);
+        assert_eq!(debugger.run(), Stop::Breakpoint(0));
+        assert_eq!(debugger.position(), 5);
+
+        assert_eq!(debugger.run(), Stop::End);
+        assert_eq!(debugger.state().depth, 60);
+
+        debugger.breakpoints = vec!["aim < 5".parse().unwrap()];
+        assert_eq!(debugger.run_back(), Stop::Breakpoint(0));
+        assert_eq!(debugger.position(), 4);
+        assert_eq!(debugger.state().aim, 2);
+        assert_eq!(debugger.run_back(), Stop::Breakpoint(0));
+        assert_eq!(debugger.position(), 1);
+        assert_eq!(debugger.run_back(), Stop::Start);
+        assert_eq!(debugger.state(), Submarine::default());
+    }
+
+    #[test]
+    fn overflow_stops_the_run() {
+        let input = vec![
+            Instruction::Down(1),
+            Instruction::Down(Coordinate::MAX),
+            Instruction::Forward(1),
+        ];
+        let mut debugger = Debugger::new(Model::Direct, input);
+
+        let overflow = OverflowError {
+            index: 1,
+            instruction: Instruction::Down(Coordinate::MAX),
+        };
+        assert_eq!(debugger.run(), Stop::Overflow(overflow));
+        assert_eq!(debugger.position(), 1);
+        assert_eq!(debugger.step(), Err(overflow));
+        assert_eq!(debugger.state().depth, 1);
+    }
+}
@@ -0,0 +1,655 @@
+use std::{fmt, str::FromStr};
+
+pub mod cost;
+pub mod debugger;
+pub mod optimise;
+pub mod parallel;
+pub mod planner;
+pub mod render;
+pub mod script;
+pub mod seabed;
+pub mod trajectory;
+pub mod validate;
+
+pub type Coordinate = i64;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Turn {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Heading {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Heading {
+    pub fn turn(self, turn: Turn) -> Heading {
+        match (self, turn) {
+            (Heading::North, Turn::Left) | (Heading::South, Turn::Right) => Heading::West,
+            (Heading::East, Turn::Left) | (Heading::West, Turn::Right) => Heading::North,
+            (Heading::South, Turn::Left) | (Heading::North, Turn::Right) => Heading::East,
+            (Heading::West, Turn::Left) | (Heading::East, Turn::Right) => Heading::South,
+        }
+    }
+
+    /// Unit step `(x, y)` for moving forward, with east as `+x` and north as `+y`.
+    pub fn unit(self) -> (Coordinate, Coordinate) {
+        match self {
+            Heading::North => (0, 1),
+            Heading::East => (1, 0),
+            Heading::South => (0, -1),
+            Heading::West => (-1, 0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Instruction {
+    Forward(Coordinate),
+    Back(Coordinate),
+    Down(Coordinate),
+    Up(Coordinate),
+    Turn(Turn),
+    /// Sideways movement, positive to starboard (right of the heading).
+    Strafe(Coordinate),
+}
+
+impl Instruction {
+    /// Whether the instruction exists in the original puzzle.
+    pub fn is_compat(&self) -> bool {
+        matches!(
+            self,
+            Instruction::Forward(_) | Instruction::Down(_) | Instruction::Up(_)
+        )
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = ParseInstructionError;
+
+    /// Accepts the canonical form written by `Display` as well as any casing and the
+    /// single-letter aliases `f`, `b`, `d`, `u`, `t` and `s`.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut tokens = text.split_whitespace();
+        let command = tokens.next().ok_or(ParseInstructionError::Empty)?;
+        let argument = tokens
+            .next()
+            .ok_or_else(|| ParseInstructionError::MissingArgument(command.to_string()))?;
+        if let Some(token) = tokens.next() {
+            return Err(ParseInstructionError::UnexpectedToken(token.to_string()));
+        }
+
+        let invalid =
+            || ParseInstructionError::InvalidArgument(command.to_string(), argument.to_string());
+        let distance = || argument.parse::<Coordinate>().map_err(|_| invalid());
+        match command.to_ascii_lowercase().as_str() {
+            "forward" | "f" => Ok(Instruction::Forward(distance()?)),
+            "back" | "b" => Ok(Instruction::Back(distance()?)),
+            "down" | "d" => Ok(Instruction::Down(distance()?)),
+            "up" | "u" => Ok(Instruction::Up(distance()?)),
+            "strafe" | "s" => Ok(Instruction::Strafe(distance()?)),
+            "turn" | "t" => match argument.to_ascii_lowercase().as_str() {
+                "left" | "l" => Ok(Instruction::Turn(Turn::Left)),
+                "right" | "r" => Ok(Instruction::Turn(Turn::Right)),
+                _ => Err(invalid()),
+            },
+            _ => Err(ParseInstructionError::UnknownCommand(command.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::Forward(distance) => write!(f, "forward {}", distance),
+            Instruction::Back(distance) => write!(f, "back {}", distance),
+            Instruction::Down(distance) => write!(f, "down {}", distance),
+            Instruction::Up(distance) => write!(f, "up {}", distance),
+            Instruction::Turn(Turn::Left) => write!(f, "turn left"),
+            Instruction::Turn(Turn::Right) => write!(f, "turn right"),
+            Instruction::Strafe(distance) => write!(f, "strafe {}", distance),
+        }
+    }
+}
+
+/// How `down` and `up` are interpreted: part 1 moves the depth directly, part 2 adjusts the aim.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Model {
+    Direct,
+    Aim,
+}
+
+impl FromStr for Model {
+    type Err = ParseModelError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text {
+            "direct" => Ok(Model::Direct),
+            "aim" => Ok(Model::Aim),
+            _ => Err(ParseModelError(text.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Submarine {
+    pub x: Coordinate,
+    pub y: Coordinate,
+    pub depth: Coordinate,
+    pub aim: Coordinate,
+    pub heading: Heading,
+}
+
+impl Default for Submarine {
+    fn default() -> Self {
+        Submarine {
+            x: 0,
+            y: 0,
+            depth: 0,
+            aim: 0,
+            heading: Heading::East,
+        }
+    }
+}
+
+/// `base` moved `distance` times along `unit`, or `None` on overflow.
+fn offset(base: Coordinate, unit: Coordinate, distance: Coordinate) -> Option<Coordinate> {
+    base.checked_add(unit.checked_mul(distance)?)
+}
+
+impl Submarine {
+    /// The state after `instruction`, or `None` when a coordinate would overflow.
+    pub fn step(&self, model: Model, instruction: &Instruction) -> Option<Submarine> {
+        match *instruction {
+            Instruction::Forward(distance) => self.travel(model, distance),
+            Instruction::Back(distance) => self.travel(model, distance.checked_neg()?),
+            Instruction::Down(delta) => self.dive(model, delta),
+            Instruction::Up(delta) => self.dive(model, delta.checked_neg()?),
+            Instruction::Turn(turn) => Some(Submarine {
+                heading: self.heading.turn(turn),
+                ..*self
+            }),
+            Instruction::Strafe(distance) => {
+                let (dx, dy) = self.heading.turn(Turn::Right).unit();
+                Some(Submarine {
+                    x: offset(self.x, dx, distance)?,
+                    y: offset(self.y, dy, distance)?,
+                    ..*self
+                })
+            }
+        }
+    }
+
+    fn travel(&self, model: Model, distance: Coordinate) -> Option<Submarine> {
+        let (dx, dy) = self.heading.unit();
+        let depth = match model {
+            Model::Direct => self.depth,
+            Model::Aim => offset(self.depth, self.aim, distance)?,
+        };
+        Some(Submarine {
+            x: offset(self.x, dx, distance)?,
+            y: offset(self.y, dy, distance)?,
+            depth,
+            ..*self
+        })
+    }
+
+    fn dive(&self, model: Model, delta: Coordinate) -> Option<Submarine> {
+        Some(match model {
+            Model::Direct => Submarine {
+                depth: self.depth.checked_add(delta)?,
+                ..*self
+            },
+            Model::Aim => Submarine {
+                aim: self.aim.checked_add(delta)?,
+                ..*self
+            },
+        })
+    }
+}
+
+/// An instruction that would take a coordinate of the submarine out of range.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct OverflowError {
+    pub index: usize,
+    pub instruction: Instruction,
+}
+
+impl fmt::Display for OverflowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "instruction {} ({}) overflows",
+            self.index, self.instruction
+        )
+    }
+}
+
+impl std::error::Error for OverflowError {}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum CompatError {
+    UnsupportedInstruction(usize, Instruction),
+    OutOfRange(usize),
+}
+
+impl fmt::Display for CompatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompatError::UnsupportedInstruction(index, instruction) => write!(
+                f,
+                "instruction {} ({:?}) is not part of the original puzzle",
+                index, instruction
+            ),
+            CompatError::OutOfRange(index) => {
+                write!(
+                    f,
+                    "instruction {} moves the submarine outside the puzzle's unsigned range",
+                    index
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for CompatError {}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ParseInstructionError {
+    Empty,
+    UnknownCommand(String),
+    MissingArgument(String),
+    InvalidArgument(String, String),
+    UnexpectedToken(String),
+}
+
+impl fmt::Display for ParseInstructionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseInstructionError::Empty => write!(f, "expected an instruction"),
+            ParseInstructionError::UnknownCommand(command) => {
+                write!(f, "unknown command `{}`", command)
+            }
+            ParseInstructionError::MissingArgument(command) => {
+                write!(f, "`{}` is missing its argument", command)
+            }
+            ParseInstructionError::InvalidArgument(command, argument) => {
+                write!(
+                    f,
+                    "`{}` is not a valid argument for `{}`",
+                    argument, command
+                )
+            }
+            ParseInstructionError::UnexpectedToken(token) => {
+                write!(f, "unexpected `{}` after the argument", token)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseInstructionError {}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseModelError(pub String);
+
+impl fmt::Display for ParseModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown model `{}`, expected direct or aim", self.0)
+    }
+}
+
+impl std::error::Error for ParseModelError {}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub error: ParseInstructionError,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses one instruction per line, ignoring blank lines and `#` comments.
+pub fn parse_input<'a>(
+    input: &'a str,
+) -> impl Iterator<Item = Result<Instruction, ParseError>> + 'a {
+    input
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let line = line.split('#').next().unwrap().trim();
+            if !line.is_empty() {
+                Some((index + 1, line))
+            } else {
+                None
+            }
+        })
+        .map(|(line, text)| text.parse().map_err(|error| ParseError { line, error }))
+}
+
+pub fn calculate_position(
+    model: Model,
+    instructions: &mut dyn Iterator<Item = Instruction>,
+) -> Result<Submarine, OverflowError> {
+    instructions
+        .enumerate()
+        .try_fold(Submarine::default(), |submarine, (index, instruction)| {
+            submarine
+                .step(model, &instruction)
+                .ok_or(OverflowError { index, instruction })
+        })
+}
+
+/// Runs `instructions` with the original puzzle semantics, returning `(horizontal, depth)`.
+pub fn calculate_compat_position(
+    model: Model,
+    instructions: &mut dyn Iterator<Item = Instruction>,
+) -> Result<(u32, u32), CompatError> {
+    let mut submarine = Submarine::default();
+    let mut position = (0, 0);
+    for (index, instruction) in instructions.enumerate() {
+        if !instruction.is_compat() {
+            return Err(CompatError::UnsupportedInstruction(index, instruction));
+        }
+        submarine = match submarine.step(model, &instruction) {
+            Some(submarine) => submarine,
+            None => return Err(CompatError::OutOfRange(index)),
+        };
+        position = match (
+            u32::try_from(submarine.x),
+            u32::try_from(submarine.depth),
+            u32::try_from(submarine.aim),
+        ) {
+            (Ok(horizontal), Ok(depth), Ok(_)) => (horizontal, depth),
+            _ => return Err(CompatError::OutOfRange(index)),
+        };
+    }
+
+    Ok(position)
+}
+
+/// The course from the puzzle description, shared by the tests of every module.
+#[cfg(test)]
+pub(crate) fn example_instructions() -> Vec<Instruction> {
+    vec![
+        Instruction::Forward(5),
+        Instruction::Down(5),
+        Instruction::Forward(8),
+        Instruction::Up(3),
+        Instruction::Down(8),
+        Instruction::Forward(2),
+    ]
+}
+
+/// Deterministic pseudo-random scripts for tests comparing two ways of evaluating instructions.
+#[cfg(test)]
+pub(crate) fn random_instructions(seed: u64, length: usize) -> Vec<Instruction> {
+    let mut state = seed | 1;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    (0..length)
+        .map(|_| {
+            let value = (next() % 11) as Coordinate - 2;
+            match next() % 8 {
+                0 | 1 => Instruction::Forward(value),
+                2 => Instruction::Back(value),
+                3 | 4 => Instruction::Down(value),
+                5 => Instruction::Up(value),
+                6 => Instruction::Strafe(value),
+                _ if value % 2 == 0 => Instruction::Turn(Turn::Left),
+                _ => Instruction::Turn(Turn::Right),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn example_parse() {
+        let input = "
+            forward 5
+            down 5
+            forward 8
+            up 3
+            down 8
+            forward 2
+        ";
+
+        assert_eq!(
+            parse_input(input).collect::<Result<Vec<_>, _>>(),
+            Ok(example_instructions())
+        );
+    }
+
+    #[test]
+    fn extended_parse() {
+        let input = "
+            back 3
+            turn left
+            turn right
+            strafe -4
+        ";
+
+        assert_eq!(
+            parse_input(input).collect::<Result<Vec<_>, _>>(),
+            Ok(vec![
+                Instruction::Back(3),
+                Instruction::Turn(Turn::Left),
+                Instruction::Turn(Turn::Right),
+                Instruction::Strafe(-4),
+            ])
+        );
+    }
+
+    #[test]
+    fn aliases_and_comments() {
+        let input = "
+            # descend slowly
+            f 5
+            FORWARD 3   # full speed
+            Down 2
+            t R
+        ";
+
+        assert_eq!(
+            parse_input(input).collect::<Result<Vec<_>, _>>(),
+            Ok(vec![
+                Instruction::Forward(5),
+                Instruction::Forward(3),
+                Instruction::Down(2),
+                Instruction::Turn(Turn::Right),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let input = "forward 5\nsideways 3\nup\ndown x\nturn around\nback 1 2\n";
+
+        assert_eq!(
+            parse_input(input).collect::<Vec<_>>(),
+            vec![
+                Ok(Instruction::Forward(5)),
+                Err(ParseError {
+                    line: 2,
+                    error: ParseInstructionError::UnknownCommand("sideways".to_string()),
+                }),
+                Err(ParseError {
+                    line: 3,
+                    error: ParseInstructionError::MissingArgument("up".to_string()),
+                }),
+                Err(ParseError {
+                    line: 4,
+                    error: ParseInstructionError::InvalidArgument(
+                        "down".to_string(),
+                        "x".to_string()
+                    ),
+                }),
+                Err(ParseError {
+                    line: 5,
+                    error: ParseInstructionError::InvalidArgument(
+                        "turn".to_string(),
+                        "around".to_string()
+                    ),
+                }),
+                Err(ParseError {
+                    line: 6,
+                    error: ParseInstructionError::UnexpectedToken("2".to_string()),
+                }),
+            ]
+        );
+        assert_eq!("".parse::<Instruction>(), Err(ParseInstructionError::Empty));
+        assert_eq!(
+            ParseError {
+                line: 2,
+                error: ParseInstructionError::UnknownCommand("sideways".to_string()),
+            }
+            .to_string(),
+            "line 2: unknown command `sideways`"
+        );
+    }
+
+    #[test]
+    fn parse_model() {
+        assert_eq!("direct".parse(), Ok(Model::Direct));
+        assert_eq!("aim".parse(), Ok(Model::Aim));
+        assert_eq!(
+            "Aim".parse::<Model>(),
+            Err(ParseModelError("Aim".to_string()))
+        );
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let instructions = vec![
+            Instruction::Forward(5),
+            Instruction::Back(0),
+            Instruction::Down(-7),
+            Instruction::Up(Coordinate::MAX),
+            Instruction::Turn(Turn::Left),
+            Instruction::Turn(Turn::Right),
+            Instruction::Strafe(Coordinate::MIN),
+        ];
+
+        for instruction in instructions {
+            assert_eq!(instruction.to_string().parse(), Ok(instruction));
+        }
+        assert_eq!(Instruction::Turn(Turn::Left).to_string(), "turn left");
+    }
+
+    #[test]
+    fn example_position_direct() {
+        let submarine =
+            calculate_position(Model::Direct, &mut example_instructions().into_iter()).unwrap();
+
+        assert_eq!((submarine.x, submarine.depth), (15, 10));
+        assert_eq!(
+            calculate_compat_position(Model::Direct, &mut example_instructions().into_iter()),
+            Ok((15, 10)),
+        );
+    }
+
+    #[test]
+    fn example_position_aim() {
+        let submarine =
+            calculate_position(Model::Aim, &mut example_instructions().into_iter()).unwrap();
+
+        assert_eq!((submarine.aim, submarine.x, submarine.depth), (10, 15, 60));
+        assert_eq!(
+            calculate_compat_position(Model::Aim, &mut example_instructions().into_iter()),
+            Ok((15, 60)),
+        );
+    }
+
+    #[test]
+    fn extended_position() {
+        let input = vec![
+            Instruction::Down(2),
+            Instruction::Forward(3),
+            Instruction::Turn(Turn::Left),
+            Instruction::Forward(4),
+            Instruction::Strafe(1),
+            Instruction::Back(1),
+            Instruction::Up(5),
+        ];
+
+        let submarine = calculate_position(Model::Aim, &mut input.into_iter()).unwrap();
+
+        assert_eq!(
+            submarine,
+            Submarine {
+                x: 4,
+                y: 3,
+                depth: 12,
+                aim: -3,
+                heading: Heading::North,
+            }
+        );
+    }
+
+    #[test]
+    fn compat_rejects_extensions() {
+        let input = vec![Instruction::Forward(1), Instruction::Turn(Turn::Left)];
+
+        assert_eq!(
+            calculate_compat_position(Model::Direct, &mut input.into_iter()),
+            Err(CompatError::UnsupportedInstruction(
+                1,
+                Instruction::Turn(Turn::Left)
+            )),
+        );
+
+        let input = vec![Instruction::Down(1), Instruction::Up(2)];
+
+        assert_eq!(
+            calculate_compat_position(Model::Direct, &mut input.into_iter()),
+            Err(CompatError::OutOfRange(1)),
+        );
+    }
+
+    #[test]
+    fn overflow_is_an_error() {
+        let input = "forward 1\nback -9223372036854775808\n";
+        let instructions: Vec<_> = parse_input(input).collect::<Result<_, _>>().unwrap();
+
+        assert_eq!(
+            calculate_position(Model::Direct, &mut instructions.into_iter()),
+            Err(OverflowError {
+                index: 1,
+                instruction: Instruction::Back(Coordinate::MIN),
+            })
+        );
+
+        let input = vec![Instruction::Down(Coordinate::MAX), Instruction::Forward(2)];
+        assert_eq!(
+            calculate_position(Model::Aim, &mut input.into_iter())
+                .unwrap_err()
+                .index,
+            1
+        );
+        assert!(Submarine::default()
+            .step(Model::Direct, &Instruction::Forward(Coordinate::MIN))
+            .is_some());
+        assert!(Submarine::default()
+            .step(Model::Direct, &Instruction::Up(Coordinate::MIN))
+            .is_none());
+    }
+}
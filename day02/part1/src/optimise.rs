@@ -0,0 +1,314 @@
+use crate::{
+    trajectory::Trajectory, Coordinate, Instruction, Model, OverflowError, Submarine, Turn,
+};
+
+/// Which states an optimised script has to share with the original.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Equivalence {
+    /// Only the final state has to match.
+    Final,
+    /// Every state the optimised script passes through must also be passed through, in the same
+    /// order, by the original script.
+    Intermediate,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Run {
+    Travel(Coordinate),
+    Dive(Coordinate),
+    /// Net quarter turns to the left, and the direction of the last turn in the run.
+    Turn(Coordinate, Turn),
+    Strafe(Coordinate),
+    /// A move whose signed amount does not fit in a `Coordinate`, such as `Back(i64::MIN)`, kept
+    /// as it is and never merged.
+    Single(Instruction),
+}
+
+impl Run {
+    fn from(instruction: &Instruction) -> Run {
+        match *instruction {
+            Instruction::Forward(distance) => Run::Travel(distance),
+            Instruction::Back(distance) => distance
+                .checked_neg()
+                .map_or(Run::Single(*instruction), Run::Travel),
+            Instruction::Down(delta) => Run::Dive(delta),
+            Instruction::Up(delta) => delta
+                .checked_neg()
+                .map_or(Run::Single(*instruction), Run::Dive),
+            Instruction::Turn(Turn::Left) => Run::Turn(1, Turn::Left),
+            Instruction::Turn(Turn::Right) => Run::Turn(-1, Turn::Right),
+            Instruction::Strafe(distance) => Run::Strafe(distance),
+        }
+    }
+
+    /// The run doing `self` then `other`, or `None` when they are on different axes or their sum
+    /// does not fit in a `Coordinate`.
+    fn merge(self, other: Run) -> Option<Run> {
+        match (self, other) {
+            (Run::Travel(a), Run::Travel(b)) => a.checked_add(b).map(Run::Travel),
+            (Run::Dive(a), Run::Dive(b)) => a.checked_add(b).map(Run::Dive),
+            (Run::Turn(a, _), Run::Turn(b, last)) => Some(Run::Turn((a + b).rem_euclid(4), last)),
+            (Run::Strafe(a), Run::Strafe(b)) => a.checked_add(b).map(Run::Strafe),
+            _ => None,
+        }
+    }
+
+    fn instructions(self) -> Vec<Instruction> {
+        match self {
+            Run::Travel(0) | Run::Dive(0) | Run::Strafe(0) => vec![],
+            Run::Travel(distance) if distance > 0 => vec![Instruction::Forward(distance)],
+            // `Coordinate::MIN` cannot be negated, but moves the same way forwards or down.
+            Run::Travel(distance) => vec![distance
+                .checked_neg()
+                .map_or(Instruction::Forward(distance), Instruction::Back)],
+            Run::Dive(delta) if delta > 0 => vec![Instruction::Down(delta)],
+            Run::Dive(delta) => vec![delta
+                .checked_neg()
+                .map_or(Instruction::Down(delta), Instruction::Up)],
+            Run::Turn(quarters, last) => match quarters.rem_euclid(4) {
+                0 => vec![],
+                1 => vec![Instruction::Turn(Turn::Left)],
+                // Turning the same way as the run ended passes through its penultimate heading.
+                2 => vec![Instruction::Turn(last); 2],
+                _ => vec![Instruction::Turn(Turn::Right)],
+            },
+            Run::Strafe(distance) => vec![Instruction::Strafe(distance)],
+            Run::Single(instruction) => vec![instruction],
+        }
+    }
+}
+
+/// Merges each run of consecutive instructions on the same axis into at most one instruction
+/// (two for a half turn), dropping runs that cancel out.
+fn merge_runs(instructions: &[Instruction]) -> Vec<Instruction> {
+    let mut runs: Vec<Run> = vec![];
+    for run in instructions.iter().map(Run::from) {
+        match runs.last().and_then(|last| last.merge(run)) {
+            Some(merged) => *runs.last_mut().unwrap() = merged,
+            None => runs.push(run),
+        }
+    }
+
+    runs.into_iter().flat_map(Run::instructions).collect()
+}
+
+/// Rewrites `instructions` into a script that is no longer and is equivalent under `model`.
+///
+/// Consecutive moves along the same axis always merge, since within such a run the aim cannot
+/// change. When only the final state matters in the direct model, depth changes commute with
+/// every other instruction, so they are also collected into a single change at the end.
+pub fn optimise(
+    model: Model,
+    instructions: &[Instruction],
+    equivalence: Equivalence,
+) -> Vec<Instruction> {
+    let mut optimised = match (model, equivalence) {
+        (Model::Direct, Equivalence::Final) => {
+            let (dives, others): (Vec<_>, Vec<_>) = instructions.iter().partition(|instruction| {
+                matches!(instruction, Instruction::Down(_) | Instruction::Up(_))
+            });
+            others.into_iter().chain(dives).collect()
+        }
+        _ => instructions.to_vec(),
+    };
+
+    loop {
+        let merged = merge_runs(&optimised);
+        if merged.len() == optimised.len() {
+            return merged;
+        }
+        optimised = merged;
+    }
+}
+
+fn states(model: Model, instructions: &[Instruction]) -> Result<Vec<Submarine>, OverflowError> {
+    Trajectory::new(model, &mut instructions.iter().copied())
+        .map(|step| step.map(|step| step.state))
+        .collect()
+}
+
+/// Checks that `optimised` is equivalent to `original` by replaying both. Scripts that overflow
+/// are never equivalent.
+pub fn equivalent(
+    model: Model,
+    original: &[Instruction],
+    optimised: &[Instruction],
+    equivalence: Equivalence,
+) -> bool {
+    let (Ok(original), Ok(optimised)) = (states(model, original), states(model, optimised)) else {
+        return false;
+    };
+    if original.last().unwrap_or(&Submarine::default())
+        != optimised.last().unwrap_or(&Submarine::default())
+    {
+        return false;
+    }
+
+    match equivalence {
+        Equivalence::Final => true,
+        Equivalence::Intermediate => {
+            let mut remaining = original.iter();
+            optimised
+                .iter()
+                .all(|state| remaining.any(|candidate| candidate == state))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{example_instructions, random_instructions};
+
+    #[test]
+    fn merges_and_cancels() {
+        let input = vec![
+            Instruction::Down(3),
+            Instruction::Down(2),
+            Instruction::Up(1),
+            Instruction::Forward(4),
+            Instruction::Back(1),
+            Instruction::Turn(Turn::Left),
+            Instruction::Turn(Turn::Right),
+            Instruction::Forward(2),
+            Instruction::Up(2),
+            Instruction::Down(2),
+            Instruction::Strafe(0),
+        ];
+
+        for model in [Model::Direct, Model::Aim] {
+            let optimised = optimise(model, &input, Equivalence::Intermediate);
+
+            assert_eq!(
+                optimised,
+                vec![Instruction::Down(4), Instruction::Forward(5)]
+            );
+            assert!(equivalent(
+                model,
+                &input,
+                &optimised,
+                Equivalence::Intermediate
+            ));
+        }
+    }
+
+    #[test]
+    fn extreme_moves() {
+        let cases = vec![
+            (
+                vec![Instruction::Forward(Coordinate::MIN)],
+                vec![Instruction::Forward(Coordinate::MIN)],
+            ),
+            (
+                vec![Instruction::Back(Coordinate::MIN)],
+                vec![Instruction::Back(Coordinate::MIN)],
+            ),
+            (
+                vec![Instruction::Back(Coordinate::MAX), Instruction::Back(1)],
+                vec![Instruction::Forward(Coordinate::MIN)],
+            ),
+            (
+                vec![Instruction::Up(Coordinate::MIN), Instruction::Up(1)],
+                vec![Instruction::Up(Coordinate::MIN), Instruction::Up(1)],
+            ),
+            (
+                vec![
+                    Instruction::Forward(Coordinate::MAX),
+                    Instruction::Forward(1),
+                    Instruction::Back(2),
+                ],
+                vec![Instruction::Forward(Coordinate::MAX - 1)],
+            ),
+        ];
+
+        for (input, expected) in cases {
+            for model in [Model::Direct, Model::Aim] {
+                for equivalence in [Equivalence::Final, Equivalence::Intermediate] {
+                    assert_eq!(optimise(model, &input, equivalence), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn final_direct_hoists_depth_changes() {
+        let input = example_instructions();
+
+        let optimised = optimise(Model::Direct, &input, Equivalence::Final);
+        assert_eq!(
+            optimised,
+            vec![Instruction::Forward(15), Instruction::Down(10)]
+        );
+        assert!(equivalent(
+            Model::Direct,
+            &input,
+            &optimised,
+            Equivalence::Final
+        ));
+        assert!(!equivalent(
+            Model::Direct,
+            &input,
+            &optimised,
+            Equivalence::Intermediate
+        ));
+
+        let optimised = optimise(Model::Aim, &input, Equivalence::Final);
+        assert_eq!(
+            optimised,
+            vec![
+                Instruction::Forward(5),
+                Instruction::Down(5),
+                Instruction::Forward(8),
+                Instruction::Down(5),
+                Instruction::Forward(2),
+            ]
+        );
+        assert!(equivalent(
+            Model::Aim,
+            &input,
+            &optimised,
+            Equivalence::Final
+        ));
+    }
+
+    #[test]
+    fn equivalent_detects_differences() {
+        let input = vec![Instruction::Down(2), Instruction::Forward(3)];
+        let reordered = vec![Instruction::Forward(3), Instruction::Down(2)];
+
+        assert!(equivalent(
+            Model::Direct,
+            &input,
+            &reordered,
+            Equivalence::Final
+        ));
+        assert!(!equivalent(
+            Model::Aim,
+            &input,
+            &reordered,
+            Equivalence::Final
+        ));
+    }
+
+    #[test]
+    fn random_scripts_stay_equivalent() {
+        for seed in 0..200 {
+            let input = random_instructions(seed, 60);
+
+            for model in [Model::Direct, Model::Aim] {
+                for equivalence in [Equivalence::Final, Equivalence::Intermediate] {
+                    let optimised = optimise(model, &input, equivalence);
+
+                    assert!(optimised.len() <= input.len());
+                    assert!(
+                        equivalent(model, &input, &optimised, equivalence),
+                        "seed {} {:?} {:?}",
+                        seed,
+                        model,
+                        equivalence
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,301 @@
+use std::thread;
+
+use crate::{
+    calculate_position, Coordinate, Heading, Instruction, Model, OverflowError, Submarine, Turn,
+};
+
+/// The effect of a block of instructions, independent of the state it starts from.
+///
+/// Movement is recorded in the block's own frame, `forward` along the heading it starts with
+/// and `right` to starboard, so it can be rotated onto whatever heading the block begins with.
+/// `depth` assumes the block starts with no aim; under the aim model each unit of `travel` adds
+/// the starting aim on top, which keeps the composition affine.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct Summary {
+    pub quarters: Coordinate,
+    pub forward: Coordinate,
+    pub right: Coordinate,
+    pub travel: Coordinate,
+    pub depth: Coordinate,
+    pub aim: Coordinate,
+}
+
+/// Expresses a vector from a frame turned `quarters` to the left in the unturned frame, or
+/// `None` when a component cannot be negated.
+fn rotate(
+    (forward, right): (Coordinate, Coordinate),
+    quarters: Coordinate,
+) -> Option<(Coordinate, Coordinate)> {
+    Some(match quarters.rem_euclid(4) {
+        0 => (forward, right),
+        1 => (right, forward.checked_neg()?),
+        2 => (forward.checked_neg()?, right.checked_neg()?),
+        _ => (right.checked_neg()?, forward),
+    })
+}
+
+/// `base + unit * amount`, or `None` on overflow.
+fn offset(base: Coordinate, unit: Coordinate, amount: Coordinate) -> Option<Coordinate> {
+    base.checked_add(unit.checked_mul(amount)?)
+}
+
+impl Summary {
+    /// The summary of `instruction` alone, or `None` when its amount cannot be negated.
+    pub fn from_instruction(model: Model, instruction: &Instruction) -> Option<Summary> {
+        let identity = Summary::default();
+        Some(match (model, *instruction) {
+            (_, Instruction::Forward(distance)) => Summary {
+                forward: distance,
+                travel: distance,
+                ..identity
+            },
+            (_, Instruction::Back(distance)) => Summary {
+                forward: distance.checked_neg()?,
+                travel: distance.checked_neg()?,
+                ..identity
+            },
+            (_, Instruction::Strafe(distance)) => Summary {
+                right: distance,
+                ..identity
+            },
+            (_, Instruction::Turn(Turn::Left)) => Summary {
+                quarters: 1,
+                ..identity
+            },
+            (_, Instruction::Turn(Turn::Right)) => Summary {
+                quarters: 3,
+                ..identity
+            },
+            (Model::Direct, Instruction::Down(delta)) => Summary {
+                depth: delta,
+                ..identity
+            },
+            (Model::Direct, Instruction::Up(delta)) => Summary {
+                depth: delta.checked_neg()?,
+                ..identity
+            },
+            (Model::Aim, Instruction::Down(delta)) => Summary {
+                aim: delta,
+                ..identity
+            },
+            (Model::Aim, Instruction::Up(delta)) => Summary {
+                aim: delta.checked_neg()?,
+                ..identity
+            },
+        })
+    }
+
+    /// The summary of `instructions`, or `None` when a field of it would overflow.
+    pub fn from_instructions(model: Model, instructions: &[Instruction]) -> Option<Summary> {
+        instructions
+            .iter()
+            .try_fold(Summary::default(), |summary, instruction| {
+                summary.then(&Summary::from_instruction(model, instruction)?)
+            })
+    }
+
+    /// The summary of running `self` followed by `next`, or `None` when a field would overflow.
+    /// This is associative, with `Summary::default()` as the identity.
+    pub fn then(&self, next: &Summary) -> Option<Summary> {
+        let (forward, right) = rotate((next.forward, next.right), self.quarters)?;
+        Some(Summary {
+            quarters: (self.quarters + next.quarters).rem_euclid(4),
+            forward: self.forward.checked_add(forward)?,
+            right: self.right.checked_add(right)?,
+            travel: self.travel.checked_add(next.travel)?,
+            depth: offset(self.depth.checked_add(next.depth)?, self.aim, next.travel)?,
+            aim: self.aim.checked_add(next.aim)?,
+        })
+    }
+
+    /// The state after running the block from `submarine`, or `None` when a coordinate of it
+    /// would overflow.
+    pub fn apply(&self, submarine: &Submarine) -> Option<Submarine> {
+        let (forward_x, forward_y) = submarine.heading.unit();
+        let (right_x, right_y) = submarine.heading.turn(Turn::Right).unit();
+        let heading = (0..self.quarters).fold(submarine.heading, |heading: Heading, _| {
+            heading.turn(Turn::Left)
+        });
+        Some(Submarine {
+            x: offset(
+                offset(submarine.x, self.forward, forward_x)?,
+                self.right,
+                right_x,
+            )?,
+            y: offset(
+                offset(submarine.y, self.forward, forward_y)?,
+                self.right,
+                right_y,
+            )?,
+            depth: offset(
+                submarine.depth.checked_add(self.depth)?,
+                submarine.aim,
+                self.travel,
+            )?,
+            aim: submarine.aim.checked_add(self.aim)?,
+            heading,
+        })
+    }
+}
+
+/// Runs `work` on each of `chunks` on its own scoped thread, returning the results in order.
+fn on_threads<'a, T: Send>(
+    chunks: &[&'a [Instruction]],
+    work: impl Fn(usize, &'a [Instruction]) -> T + Sync,
+) -> Vec<T> {
+    thread::scope(|scope| {
+        let handles: Vec<_> = chunks
+            .iter()
+            .enumerate()
+            .map(|(index, &chunk)| {
+                let work = &work;
+                scope.spawn(move || work(index, chunk))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+/// Evaluates `instructions` by summarising contiguous chunks on up to `threads` scoped threads,
+/// composing the summaries in order to find the state each chunk starts from, and replaying the
+/// chunks from those states in parallel, so overflow is reported exactly as
+/// [`calculate_position`] reports it.
+///
+/// When a summary itself overflows, which can happen even though no state does, the
+/// instructions are run sequentially instead.
+pub fn parallel_position(
+    model: Model,
+    instructions: &[Instruction],
+    threads: usize,
+) -> Result<Submarine, OverflowError> {
+    let chunk_size = instructions.len().div_ceil(threads.max(1)).max(1);
+    let chunks: Vec<&[Instruction]> = instructions.chunks(chunk_size).collect();
+
+    let summaries = on_threads(&chunks, |_, chunk| Summary::from_instructions(model, chunk));
+    let starts = summaries
+        .iter()
+        .try_fold(vec![Submarine::default()], |mut starts, summary| {
+            let next = summary.as_ref()?.apply(starts.last()?)?;
+            starts.push(next);
+            Some(starts)
+        });
+    let Some(starts) = starts else {
+        return calculate_position(model, &mut instructions.iter().copied());
+    };
+
+    let ends = on_threads(&chunks, |index, chunk| {
+        chunk
+            .iter()
+            .enumerate()
+            .try_fold(starts[index], |submarine, (offset, instruction)| {
+                submarine.step(model, instruction).ok_or(OverflowError {
+                    index: index * chunk_size + offset,
+                    instruction: *instruction,
+                })
+            })
+    });
+    // The first overflow, or the state the last chunk ends in.
+    ends.into_iter()
+        .try_fold(Submarine::default(), |_, end| end)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{example_instructions, random_instructions};
+
+    #[test]
+    fn example_position() {
+        let input = example_instructions();
+
+        let submarine = parallel_position(Model::Aim, &input, 3).unwrap();
+
+        assert_eq!((submarine.x, submarine.depth), (15, 60));
+        assert_eq!(
+            parallel_position(Model::Direct, &input, 4).unwrap().depth,
+            10
+        );
+        assert_eq!(
+            parallel_position(Model::Aim, &[], 4),
+            Ok(Submarine::default())
+        );
+    }
+
+    #[test]
+    fn composition_is_associative() {
+        for seed in 0..50 {
+            let [a, b, c] = [0, 1, 2].map(|offset| {
+                Summary::from_instructions(Model::Aim, &random_instructions(seed * 3 + offset, 20))
+                    .unwrap()
+            });
+
+            assert_eq!(a.then(&b).unwrap().then(&c), a.then(&b.then(&c).unwrap()));
+            assert_eq!(a.then(&Summary::default()), Some(a));
+            assert_eq!(Summary::default().then(&a), Some(a));
+        }
+    }
+
+    #[test]
+    fn matches_sequential_fold() {
+        for seed in 0..100 {
+            let input = random_instructions(seed, 500);
+
+            for model in [Model::Direct, Model::Aim] {
+                let expected = calculate_position(model, &mut input.iter().copied());
+                for threads in [1, 2, 3, 7, 16] {
+                    assert_eq!(
+                        parallel_position(model, &input, threads),
+                        expected,
+                        "seed {} {:?} threads {}",
+                        seed,
+                        model,
+                        threads
+                    );
+                }
+            }
+        }
+    }
+    #[test]
+    fn overflow_matches_sequential_fold() {
+        let cases = vec![
+            vec![Instruction::Back(Coordinate::MIN)],
+            vec![Instruction::Down(Coordinate::MAX), Instruction::Forward(2)],
+            vec![
+                Instruction::Forward(Coordinate::MAX),
+                Instruction::Forward(1),
+                Instruction::Back(1),
+            ],
+            vec![
+                Instruction::Forward(Coordinate::MAX),
+                Instruction::Turn(Turn::Left),
+                Instruction::Turn(Turn::Left),
+                Instruction::Forward(Coordinate::MAX),
+            ],
+            vec![
+                Instruction::Up(Coordinate::MAX),
+                Instruction::Up(1),
+                Instruction::Down(1),
+                Instruction::Forward(1),
+            ],
+        ];
+
+        for input in cases {
+            for model in [Model::Direct, Model::Aim] {
+                let expected = calculate_position(model, &mut input.iter().copied());
+                for threads in [1, 2, 3, 4] {
+                    assert_eq!(
+                        parallel_position(model, &input, threads),
+                        expected,
+                        "{:?} {:?} threads {}",
+                        input,
+                        model,
+                        threads
+                    );
+                }
+            }
+        }
+    }
+}
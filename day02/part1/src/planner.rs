@@ -0,0 +1,535 @@
+use std::fmt;
+
+use crate::{
+    calculate_position,
+    cost::{calculate_cost, Cost, CostModel},
+    Coordinate, Instruction, Model, OverflowError, Submarine,
+};
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct Limits {
+    pub max_depth: Option<Coordinate>,
+    /// Largest argument allowed on a single instruction.
+    pub max_magnitude: Option<Coordinate>,
+}
+
+/// How `plan_for` chooses between the candidate plans for a target.
+///
+/// Both objectives only rank the few short candidates `plan` considers, so `CandidateCost` picks
+/// the cheapest of those rather than searching for the cheapest plan overall, which may take
+/// more instructions.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Objective {
+    #[default]
+    InstructionCount,
+    CandidateCost(CostModel),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PlanError {
+    InvalidMagnitude(Coordinate),
+    TooDeep(Coordinate),
+    VerificationFailed(Submarine),
+    Overflow(OverflowError),
+}
+
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlanError::InvalidMagnitude(magnitude) => {
+                write!(f, "maximum magnitude {} must be positive", magnitude)
+            }
+            PlanError::TooDeep(max_depth) => {
+                write!(f, "no plan stays within the maximum depth of {}", max_depth)
+            }
+            PlanError::VerificationFailed(submarine) => {
+                write!(f, "plan finished at {:?} instead of the target", submarine)
+            }
+            PlanError::Overflow(error) => write!(f, "plan cannot be run: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for PlanError {}
+
+fn travel(distance: Coordinate) -> Option<Instruction> {
+    match distance {
+        0 => None,
+        distance if distance > 0 => Some(Instruction::Forward(distance)),
+        // `back` cannot express the most negative distance.
+        distance => Some(
+            distance
+                .checked_neg()
+                .map_or(Instruction::Forward(distance), Instruction::Back),
+        ),
+    }
+}
+
+fn dive(delta: Coordinate) -> Option<Instruction> {
+    match delta {
+        0 => None,
+        delta if delta > 0 => Some(Instruction::Down(delta)),
+        delta => Some(
+            delta
+                .checked_neg()
+                .map_or(Instruction::Down(delta), Instruction::Up),
+        ),
+    }
+}
+
+/// Largest factor `divisors` tries by trial division.
+const TRIAL_DIVISION_LIMIT: u64 = 1 << 20;
+
+/// Divisors of `value` no larger than `limit` that fit in a `Coordinate`, largest first.
+///
+/// Factorises by trial division, dividing out each factor as it is found so values made of small
+/// factors are quick even when they are large. The search stops at `limit`, as no divisor within
+/// it has a larger factor, and at `TRIAL_DIVISION_LIMIT`, beyond which what remains is taken to be
+/// prime; a value with two or more prime factors above that loses the divisors made of them, but
+/// `1` is always kept.
+fn divisors(value: Coordinate, limit: u64) -> Vec<Coordinate> {
+    if value == 0 {
+        return vec![];
+    }
+
+    let mut remaining = value.unsigned_abs();
+    let mut divisors = vec![1];
+    let include = |divisors: &mut Vec<u64>, factor: u64, power: u32| {
+        let existing = divisors.len();
+        for exponent in 1..=power {
+            for index in 0..existing {
+                divisors.push(divisors[index] * factor.pow(exponent));
+            }
+        }
+    };
+    let mut factor = 2;
+    while factor <= limit.min(TRIAL_DIVISION_LIMIT) && factor * factor <= remaining {
+        let mut power = 0;
+        while remaining.is_multiple_of(factor) {
+            remaining /= factor;
+            power += 1;
+        }
+        include(&mut divisors, factor, power);
+        factor += 1;
+    }
+    if remaining > 1 {
+        include(&mut divisors, remaining, 1);
+    }
+
+    divisors.sort_unstable_by(|a, b| b.cmp(a));
+    divisors
+        .into_iter()
+        .filter(|&divisor| divisor <= limit)
+        .filter_map(|divisor| Coordinate::try_from(divisor).ok())
+        .collect()
+}
+
+/// Shortest plans reaching the target when instruction arguments are unbounded.
+///
+/// In the direct model depth and horizontal movement are independent. In the aim model a single
+/// aim change is enough: travel part of the way level, set the aim so the remaining travel covers
+/// the whole depth, then finish the travel. This needs a divisor of the depth no larger than the
+/// horizontal distance, and `1` always qualifies, so every target takes at most three
+/// instructions. With no horizontal distance the submarine goes out and back on opposite aims.
+fn candidates(
+    model: Model,
+    (horizontal, depth): (Coordinate, Coordinate),
+) -> Vec<Vec<Instruction>> {
+    let plans = match model {
+        Model::Direct => vec![vec![travel(horizontal), dive(depth)]],
+        Model::Aim if depth == 0 => vec![vec![travel(horizontal)]],
+        Model::Aim if horizontal == 0 => divisors(depth, u64::MAX)
+            .into_iter()
+            .filter_map(|divisor| {
+                let aim = (depth / divisor).checked_neg()?;
+                Some(vec![travel(divisor), dive(aim), travel(-divisor)])
+            })
+            .collect(),
+        Model::Aim => divisors(depth, horizontal.unsigned_abs())
+            .into_iter()
+            .filter_map(|divisor| {
+                let last = divisor * horizontal.signum();
+                let aim = depth.checked_div(last)?;
+                Some(vec![travel(horizontal - last), dive(aim), travel(last)])
+            })
+            .collect(),
+    };
+
+    plans
+        .into_iter()
+        .map(|plan| plan.into_iter().flatten().collect())
+        .collect()
+}
+
+/// Builds an instruction of one command from its argument.
+type Rebuild = fn(Coordinate) -> Instruction;
+
+/// The argument of `instruction` and how to build the same command with another one.
+fn argument(instruction: Instruction) -> Option<(Coordinate, Rebuild)> {
+    match instruction {
+        Instruction::Forward(distance) => Some((distance, Instruction::Forward)),
+        Instruction::Back(distance) => Some((distance, Instruction::Back)),
+        Instruction::Down(delta) => Some((delta, Instruction::Down)),
+        Instruction::Up(delta) => Some((delta, Instruction::Up)),
+        Instruction::Strafe(distance) => Some((distance, Instruction::Strafe)),
+        Instruction::Turn(_) => None,
+    }
+}
+
+/// How many instructions `split` turns `instruction` into.
+fn pieces(instruction: Instruction, max_magnitude: Option<Coordinate>) -> u64 {
+    match (argument(instruction), max_magnitude) {
+        (Some((magnitude, _)), Some(max_magnitude)) => magnitude
+            .unsigned_abs()
+            .div_ceil(max_magnitude.unsigned_abs())
+            .max(1),
+        _ => 1,
+    }
+}
+
+/// Splits `instruction` into as few instructions as possible with no argument larger than
+/// `max_magnitude`, full-sized ones first.
+fn split(instruction: Instruction, max_magnitude: Coordinate) -> impl Iterator<Item = Instruction> {
+    let (full, last) = match argument(instruction) {
+        Some((magnitude, rebuild)) => {
+            let piece = max_magnitude * magnitude.signum();
+            let full = pieces(instruction, Some(max_magnitude)) - 1;
+            (
+                std::iter::repeat_n(rebuild(piece), full as usize),
+                rebuild(magnitude - piece * full as Coordinate),
+            )
+        }
+        None => (std::iter::repeat_n(instruction, 0), instruction),
+    };
+    full.chain(std::iter::once(last))
+}
+
+/// The cost of `plan` once split under `max_magnitude`, or `None` if it overflows.
+///
+/// The pieces of an instruction cover the same movement as the whole instruction, so they cost
+/// the same except for the aim change each extra `down` or `up` piece makes under the aim model.
+fn split_cost(
+    model: Model,
+    plan: &[Instruction],
+    max_magnitude: Option<Coordinate>,
+    costs: &CostModel,
+) -> Option<Cost> {
+    let (_, cost) = calculate_cost(model, &mut plan.iter().copied(), costs).ok()?;
+    if model == Model::Direct {
+        return Some(cost);
+    }
+    plan.iter()
+        .filter(|instruction| matches!(instruction, Instruction::Down(_) | Instruction::Up(_)))
+        .try_fold(cost, |cost, &instruction| {
+            let extra = Cost::try_from(pieces(instruction, max_magnitude) - 1).ok()?;
+            cost.checked_add(costs.aim_change.checked_mul(extra)?)
+        })
+}
+
+/// The deepest point of `plan`, or `None` if it overflows.
+fn max_depth(model: Model, plan: &[Instruction]) -> Option<Coordinate> {
+    plan.iter()
+        .try_fold(
+            (Submarine::default(), 0),
+            |(state, deepest), instruction| {
+                let state = state.step(model, instruction)?;
+                Some((state, deepest.max(state.depth)))
+            },
+        )
+        .map(|(_, deepest)| deepest)
+}
+
+/// Finds a short instruction sequence taking the submarine from the origin to
+/// `(horizontal, depth)`, verified by re-running it through `calculate_position`.
+///
+/// Without limits the plan is as short as possible. Limits are applied to the same few candidate
+/// plans, dropping those that go too deep and splitting instructions whose arguments are too
+/// large, so a limited plan is the shortest of those candidates but a longer search could
+/// sometimes find a shorter one, or find a plan where this reports `TooDeep`.
+pub fn plan(
+    model: Model,
+    target: (Coordinate, Coordinate),
+    limits: &Limits,
+) -> Result<Vec<Instruction>, PlanError> {
+    plan_for(model, target, limits, &Objective::InstructionCount)
+}
+
+/// Like `plan`, but picks whichever candidate plan scores best under `objective`, keeping the
+/// earliest candidate on ties.
+pub fn plan_for(
+    model: Model,
+    target: (Coordinate, Coordinate),
+    limits: &Limits,
+    objective: &Objective,
+) -> Result<Vec<Instruction>, PlanError> {
+    if let Some(magnitude) = limits.max_magnitude.filter(|&magnitude| magnitude <= 0) {
+        return Err(PlanError::InvalidMagnitude(magnitude));
+    }
+
+    // Splitting an instruction keeps the submarine on the same path, so candidates are checked
+    // and scored whole, and only the chosen one is split.
+    let best = candidates(model, target)
+        .into_iter()
+        .filter(|plan| match limits.max_depth {
+            Some(limit) => max_depth(model, plan).is_some_and(|depth| depth <= limit),
+            None => true,
+        })
+        .min_by_key(|plan| match objective {
+            Objective::InstructionCount => plan
+                .iter()
+                .map(|&instruction| i128::from(pieces(instruction, limits.max_magnitude)))
+                .sum(),
+            // Plans whose cost overflows rank last.
+            Objective::CandidateCost(costs) => {
+                split_cost(model, plan, limits.max_magnitude, costs).map_or(i128::MAX, i128::from)
+            }
+        });
+
+    let best: Vec<_> = match (best, limits.max_magnitude) {
+        (Some(plan), Some(max_magnitude)) => plan
+            .into_iter()
+            .flat_map(|instruction| split(instruction, max_magnitude))
+            .collect(),
+        (Some(plan), None) => plan,
+        (None, _) => return Err(PlanError::TooDeep(limits.max_depth.unwrap_or_default())),
+    };
+
+    let submarine =
+        calculate_position(model, &mut best.iter().copied()).map_err(PlanError::Overflow)?;
+    if (submarine.x, submarine.y, submarine.depth) != (target.0, 0, target.1) {
+        return Err(PlanError::VerificationFailed(submarine));
+    }
+
+    Ok(best)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn reaches(model: Model, plan: &[Instruction], target: (Coordinate, Coordinate)) -> bool {
+        let submarine = calculate_position(model, &mut plan.iter().copied()).unwrap();
+        (submarine.x, submarine.depth) == target
+    }
+
+    #[test]
+    fn example_targets() {
+        assert_eq!(
+            plan(Model::Direct, (15, 10), &Limits::default()),
+            Ok(vec![Instruction::Forward(15), Instruction::Down(10)])
+        );
+        assert_eq!(
+            plan(Model::Aim, (15, 60), &Limits::default()),
+            Ok(vec![Instruction::Down(4), Instruction::Forward(15)])
+        );
+        assert_eq!(plan(Model::Aim, (0, 0), &Limits::default()), Ok(vec![]));
+    }
+
+    #[test]
+    fn aim_needs_three_instructions() {
+        assert_eq!(
+            plan(Model::Aim, (5, 7), &Limits::default()),
+            Ok(vec![
+                Instruction::Forward(4),
+                Instruction::Down(7),
+                Instruction::Forward(1),
+            ])
+        );
+        assert_eq!(
+            plan(Model::Aim, (0, 6), &Limits::default()),
+            Ok(vec![
+                Instruction::Forward(6),
+                Instruction::Up(1),
+                Instruction::Back(6),
+            ])
+        );
+    }
+
+    #[test]
+    fn all_small_targets_reachable() {
+        for model in [Model::Direct, Model::Aim] {
+            for horizontal in -12..=12 {
+                for depth in -12..=12 {
+                    let target = (horizontal, depth);
+                    let limits = Limits {
+                        max_depth: None,
+                        max_magnitude: Some(4),
+                    };
+                    let instructions = plan(model, target, &limits).unwrap();
+                    assert!(reaches(model, &instructions, target), "{:?}", target);
+
+                    let unlimited = plan(model, target, &Limits::default()).unwrap();
+                    assert!(unlimited.len() <= 3, "{:?}: {:?}", target, unlimited);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn magnitude_limit_picks_shortest_split() {
+        let limits = Limits {
+            max_depth: None,
+            max_magnitude: Some(5),
+        };
+
+        let instructions = plan(Model::Aim, (10, 12), &limits).unwrap();
+
+        assert_eq!(
+            instructions,
+            vec![
+                Instruction::Forward(4),
+                Instruction::Down(2),
+                Instruction::Forward(5),
+                Instruction::Forward(1),
+            ]
+        );
+        assert_eq!(
+            plan(
+                Model::Aim,
+                (10, 12),
+                &Limits {
+                    max_depth: None,
+                    max_magnitude: Some(0),
+                }
+            ),
+            Err(PlanError::InvalidMagnitude(0))
+        );
+    }
+
+    #[test]
+    fn split_pieces() {
+        assert_eq!(
+            split(Instruction::Down(12), 5).collect::<Vec<_>>(),
+            vec![
+                Instruction::Down(5),
+                Instruction::Down(5),
+                Instruction::Down(2),
+            ]
+        );
+        assert_eq!(split(Instruction::Forward(-7), 5).count(), 2);
+        assert_eq!(
+            split(Instruction::Forward(Coordinate::MIN), Coordinate::MAX).last(),
+            Some(Instruction::Forward(-1))
+        );
+        assert_eq!(
+            split(Instruction::Turn(crate::Turn::Left), 1).collect::<Vec<_>>(),
+            vec![Instruction::Turn(crate::Turn::Left)]
+        );
+        assert_eq!(pieces(Instruction::Down(1 << 60), Some(1)), 1 << 60);
+    }
+
+    #[test]
+    fn split_cost_counts_aim_changes() {
+        let costs = CostModel {
+            drag: 1,
+            aim_change: 100,
+            ..CostModel::default()
+        };
+        let plan = [
+            Instruction::Forward(3),
+            Instruction::Down(12),
+            Instruction::Forward(7),
+        ];
+
+        for model in [Model::Direct, Model::Aim] {
+            let split_plan: Vec<_> = plan
+                .iter()
+                .flat_map(|&instruction| split(instruction, 5))
+                .collect();
+            assert_eq!(
+                split_cost(model, &plan, Some(5), &costs),
+                calculate_cost(model, &mut split_plan.into_iter(), &costs)
+                    .ok()
+                    .map(|(_, cost)| cost)
+            );
+        }
+        assert_eq!(
+            split_cost(Model::Aim, &plan, Some(5), &costs),
+            Some(3 + 12 + 7 + 300 + 12 * (1 + 2 + 3 + 4 + 5 + 6 + 7))
+        );
+    }
+
+    #[test]
+    fn cost_objective_prefers_late_descent() {
+        let objective = Objective::CandidateCost(CostModel {
+            drag: 1,
+            ..CostModel::default()
+        });
+
+        assert_eq!(
+            plan_for(Model::Aim, (10, 12), &Limits::default(), &objective),
+            Ok(vec![
+                Instruction::Forward(8),
+                Instruction::Down(6),
+                Instruction::Forward(2),
+            ])
+        );
+        assert_eq!(
+            plan_for(
+                Model::Aim,
+                (10, 12),
+                &Limits::default(),
+                &Objective::InstructionCount
+            ),
+            Ok(vec![
+                Instruction::Forward(4),
+                Instruction::Down(2),
+                Instruction::Forward(6),
+            ])
+        );
+    }
+
+    #[test]
+    fn max_depth_limit() {
+        let limits = Limits {
+            max_depth: Some(50),
+            max_magnitude: None,
+        };
+
+        assert_eq!(
+            plan(Model::Aim, (15, 60), &limits),
+            Err(PlanError::TooDeep(50))
+        );
+        assert!(plan(Model::Aim, (15, 45), &limits).is_ok());
+        assert!(plan(Model::Direct, (15, -10), &limits).is_ok());
+    }
+
+    #[test]
+    fn extreme_targets() {
+        for target in [
+            (5, Coordinate::MIN),
+            (-1, Coordinate::MIN),
+            (0, Coordinate::MIN),
+            (Coordinate::MIN, 0),
+            (Coordinate::MIN, 1 << 40),
+            (Coordinate::MAX, Coordinate::MAX),
+        ] {
+            for model in [Model::Direct, Model::Aim] {
+                if let Ok(instructions) = plan(model, target, &Limits::default()) {
+                    assert!(reaches(model, &instructions, target), "{:?}", target);
+                }
+            }
+        }
+        assert_eq!(
+            plan(Model::Aim, (1, Coordinate::MIN), &Limits::default()),
+            Ok(vec![
+                Instruction::Down(Coordinate::MIN),
+                Instruction::Forward(1)
+            ])
+        );
+        assert_eq!(divisors(Coordinate::MIN, u64::MAX).len(), 63);
+        assert_eq!(divisors(-12, u64::MAX), vec![12, 6, 4, 3, 2, 1]);
+        assert_eq!(divisors(-12, 5), vec![4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn large_prime_depth() {
+        let depth = (1 << 61) - 1;
+
+        assert_eq!(divisors(depth, u64::MAX), vec![depth, 1]);
+        for target in [(5, depth), (0, depth)] {
+            let instructions = plan(Model::Aim, target, &Limits::default()).unwrap();
+            assert!(reaches(Model::Aim, &instructions, target), "{:?}", target);
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use std::fmt::Write;
+
+use crate::{trajectory::Step, Coordinate, Instruction, Model, Submarine};
+
+const SVG_WIDTH: f64 = 800.0;
+const SVG_HEIGHT: f64 = 400.0;
+const SVG_MARGIN: f64 = 20.0;
+
+pub fn colour(instruction: &Instruction) -> &'static str {
+    match instruction {
+        Instruction::Forward(_) => "#1f77b4",
+        Instruction::Back(_) => "#ff7f0e",
+        Instruction::Down(_) => "#2ca02c",
+        Instruction::Up(_) => "#d62728",
+        Instruction::Turn(_) => "#9467bd",
+        Instruction::Strafe(_) => "#8c564b",
+    }
+}
+
+fn ansi_colour(instruction: &Instruction) -> u8 {
+    match instruction {
+        Instruction::Forward(_) => 34,
+        Instruction::Back(_) => 33,
+        Instruction::Down(_) => 32,
+        Instruction::Up(_) => 31,
+        Instruction::Turn(_) => 35,
+        Instruction::Strafe(_) => 36,
+    }
+}
+
+fn symbol(instruction: &Instruction) -> char {
+    match instruction {
+        Instruction::Forward(_) => 'f',
+        Instruction::Back(_) => 'b',
+        Instruction::Down(_) => 'd',
+        Instruction::Up(_) => 'u',
+        Instruction::Turn(_) => 't',
+        Instruction::Strafe(_) => 's',
+    }
+}
+
+fn is_aim_change(model: Model, instruction: &Instruction) -> bool {
+    model == Model::Aim && matches!(instruction, Instruction::Down(_) | Instruction::Up(_))
+}
+
+/// Side profile bounds as `(min_x, max_x, min_depth, max_depth)`, including the origin.
+fn bounds(steps: &[Step]) -> (Coordinate, Coordinate, Coordinate, Coordinate) {
+    steps
+        .iter()
+        .fold((0, 0, 0, 0), |(min_x, max_x, min_d, max_d), step| {
+            (
+                min_x.min(step.state.x),
+                max_x.max(step.state.x),
+                min_d.min(step.state.depth),
+                max_d.max(step.state.depth),
+            )
+        })
+}
+
+fn segments(steps: &[Step]) -> impl Iterator<Item = (Submarine, &Step)> {
+    std::iter::once(Submarine::default())
+        .chain(steps.iter().map(|step| step.state))
+        .zip(steps.iter())
+}
+
+/// Renders the horizontal position against depth, with depth increasing down the page.
+pub fn to_svg(model: Model, steps: &[Step]) -> String {
+    let (min_x, max_x, min_depth, max_depth) = bounds(steps);
+    // Spans are taken in `f64`, as the difference of two coordinates may not fit in one.
+    let (min_x, max_x) = (min_x as f64, max_x as f64);
+    let (min_depth, max_depth) = (min_depth as f64, max_depth as f64);
+    let scale_x = (SVG_WIDTH - 2.0 * SVG_MARGIN) / (max_x - min_x).max(1.0);
+    let scale_y = (SVG_HEIGHT - 2.0 * SVG_MARGIN) / (max_depth - min_depth).max(1.0);
+    let project = |state: &Submarine| {
+        (
+            SVG_MARGIN + (state.x as f64 - min_x) * scale_x,
+            SVG_MARGIN + (state.depth as f64 - min_depth) * scale_y,
+        )
+    };
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+        SVG_WIDTH, SVG_HEIGHT, SVG_WIDTH, SVG_HEIGHT
+    )
+    .unwrap();
+
+    for (before, step) in segments(steps) {
+        let (x1, y1) = project(&before);
+        let (x2, y2) = project(&step.state);
+        if is_aim_change(model, &step.instruction) {
+            writeln!(
+                svg,
+                r#"  <circle cx="{:.2}" cy="{:.2}" r="3" fill="{}"><title>{}: {:?}</title></circle>"#,
+                x2,
+                y2,
+                colour(&step.instruction),
+                step.index,
+                step.instruction
+            )
+            .unwrap();
+        } else if (x1, y1) != (x2, y2) {
+            writeln!(
+                svg,
+                r#"  <line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="{}" stroke-width="2"><title>{}: {:?}</title></line>"#,
+                x1,
+                y1,
+                x2,
+                y2,
+                colour(&step.instruction),
+                step.index,
+                step.instruction
+            )
+            .unwrap();
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Renders the side profile as a `width` by `height` grid of characters.
+///
+/// Each cell shows the first letter of the instruction that last crossed it, aim changes are
+/// drawn on top and marked with `v` and `^`, and with `ansi` set each cell is coloured by instruction type.
+/// A grid with no columns or no rows renders as an empty string.
+pub fn to_ascii(model: Model, steps: &[Step], width: usize, height: usize, ansi: bool) -> String {
+    if width == 0 || height == 0 {
+        return String::new();
+    }
+
+    let (min_x, max_x, min_depth, max_depth) = bounds(steps);
+    // As in `to_svg`, spans are taken in `f64` so they cannot overflow.
+    let (min_x, max_x) = (min_x as f64, max_x as f64);
+    let (min_depth, max_depth) = (min_depth as f64, max_depth as f64);
+    let span_x = (max_x - min_x).max(1.0);
+    let span_y = (max_depth - min_depth).max(1.0);
+    let project = |x: f64, depth: f64| {
+        (
+            (((x - min_x) / span_x) * (width - 1) as f64).round() as usize,
+            (((depth - min_depth) / span_y) * (height - 1) as f64).round() as usize,
+        )
+    };
+
+    let mut grid: Vec<Vec<Option<(char, &Instruction)>>> = vec![vec![None; width]; height];
+    let mut markers = vec![];
+    for (before, step) in segments(steps) {
+        if is_aim_change(model, &step.instruction) {
+            let marker = match step.instruction {
+                Instruction::Up(_) => '^',
+                _ => 'v',
+            };
+            markers.push((marker, step));
+            continue;
+        }
+
+        let (x1, y1) = (before.x as f64, before.depth as f64);
+        let (x2, y2) = (step.state.x as f64, step.state.depth as f64);
+        let (c1, r1) = project(x1, y1);
+        let (c2, r2) = project(x2, y2);
+        let samples = c1.abs_diff(c2).max(r1.abs_diff(r2));
+        if samples == 0 && (x1, y1) == (x2, y2) {
+            continue;
+        }
+        for sample in 0..=samples {
+            let t = sample as f64 / samples.max(1) as f64;
+            let (column, row) = project(x1 + (x2 - x1) * t, y1 + (y2 - y1) * t);
+            grid[row][column] = Some((symbol(&step.instruction), &step.instruction));
+        }
+    }
+    for (marker, step) in markers {
+        let (column, row) = project(step.state.x as f64, step.state.depth as f64);
+        grid[row][column] = Some((marker, &step.instruction));
+    }
+
+    let mut plot = String::new();
+    for row in grid {
+        for cell in row {
+            match cell {
+                Some((character, instruction)) if ansi => write!(
+                    plot,
+                    "\x1b[{}m{}\x1b[0m",
+                    ansi_colour(instruction),
+                    character
+                )
+                .unwrap(),
+                Some((character, _)) => plot.push(character),
+                None => plot.push(' '),
+            }
+        }
+        plot.push('\n');
+    }
+    plot
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{example_instructions, trajectory::Trajectory};
+
+    #[test]
+    fn example_svg() {
+        let steps: Vec<_> = Trajectory::new(Model::Aim, &mut example_instructions().into_iter())
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let svg = to_svg(Model::Aim, &steps);
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<line").count(), 3);
+        assert_eq!(svg.matches("<circle").count(), 3);
+        assert!(svg
+            .contains(r##"<line x1="20.00" y1="20.00" x2="273.33" y2="20.00" stroke="#1f77b4""##));
+    }
+
+    #[test]
+    fn example_ascii_direct() {
+        let steps: Vec<_> = Trajectory::new(Model::Direct, &mut example_instructions().into_iter())
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let plot = to_ascii(Model::Direct, &steps, 16, 11, false);
+
+        assert_eq!(
+            plot,
+            [
+                "fffffd          ",
+                "     d          ",
+                "     d       d  ",
+                "     d       d  ",
+                "     d       d  ",
+                "     ffffffffd  ",
+                "             d  ",
+                "             d  ",
+                "             d  ",
+                "             d  ",
+                "             fff",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn example_ascii_aim_markers() {
+        let steps: Vec<_> = Trajectory::new(Model::Aim, &mut example_instructions().into_iter())
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let plot = to_ascii(Model::Aim, &steps, 16, 7, true);
+
+        assert_eq!(plot.matches("\x1b[32mv\x1b[0m").count(), 2);
+        assert!(plot.contains("\x1b[34mf\x1b[0m"));
+        assert_eq!(plot.lines().count(), 7);
+    }
+
+    #[test]
+    fn extreme_span() {
+        let input = vec![
+            Instruction::Forward(Coordinate::MAX),
+            Instruction::Back(Coordinate::MAX),
+            Instruction::Back(Coordinate::MAX),
+        ];
+        let steps: Vec<_> = Trajectory::new(Model::Direct, &mut input.into_iter())
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let svg = to_svg(Model::Direct, &steps);
+
+        assert_eq!(svg.matches("<line").count(), 3);
+        assert!(svg.contains(r#"x1="400.00" y1="20.00" x2="780.00""#));
+        assert_eq!(to_ascii(Model::Direct, &steps, 5, 1, false), "bbbbb\n");
+    }
+
+    #[test]
+    fn empty_grid() {
+        let steps: Vec<_> = Trajectory::new(Model::Aim, &mut example_instructions().into_iter())
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        for (width, height) in [(0, 7), (16, 0), (0, 0)] {
+            assert_eq!(to_ascii(Model::Aim, &steps, width, height, false), "");
+        }
+        assert_eq!(to_ascii(Model::Aim, &steps, 1, 1, false), "v\n");
+    }
+}
@@ -0,0 +1,430 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{Coordinate, Instruction, ParseInstructionError};
+
+/// Most instructions a script may expand to, so that nested repeats cannot exhaust memory.
+pub const MAX_INSTRUCTIONS: usize = 1 << 20;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ScriptErrorKind {
+    Instruction(ParseInstructionError),
+    UnknownName(String),
+    InvalidName(String),
+    Redefined(String),
+    InvalidCount(String),
+    InvalidStatement(String),
+    UnclosedBlock,
+    UnexpectedClose,
+    TooLong,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ScriptError {
+    pub line: usize,
+    pub kind: ScriptErrorKind,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ScriptErrorKind::Instruction(error) => write!(f, "{}", error),
+            ScriptErrorKind::UnknownName(name) => write!(f, "`{}` is not defined", name),
+            ScriptErrorKind::InvalidName(name) => write!(f, "`{}` is not a valid name", name),
+            ScriptErrorKind::Redefined(name) => write!(f, "`{}` is already defined", name),
+            ScriptErrorKind::InvalidCount(count) => {
+                write!(f, "`{}` is not a valid repeat count", count)
+            }
+            ScriptErrorKind::InvalidStatement(statement) => {
+                write!(f, "cannot understand `{}`", statement)
+            }
+            ScriptErrorKind::UnclosedBlock => write!(f, "block is never closed"),
+            ScriptErrorKind::UnexpectedClose => write!(f, "`}}` does not close any block"),
+            ScriptErrorKind::TooLong => write!(
+                f,
+                "script expands to more than {} instructions",
+                MAX_INSTRUCTIONS
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+/// Words a script already gives a meaning to, which cannot be used as names in any casing.
+const KEYWORDS: [&str; 11] = [
+    "forward", "back", "down", "up", "strafe", "turn", "left", "right", "let", "macro", "repeat",
+];
+
+fn is_name(token: &str) -> bool {
+    let mut characters = token.chars();
+    matches!(characters.next(), Some(first) if first.is_ascii_alphabetic() || first == '_')
+        && characters.all(|character| character.is_ascii_alphanumeric() || character == '_')
+}
+
+fn is_keyword(token: &str) -> bool {
+    KEYWORDS
+        .iter()
+        .any(|keyword| keyword.eq_ignore_ascii_case(token))
+}
+
+fn is_turn(command: &str) -> bool {
+    command.eq_ignore_ascii_case("turn") || command.eq_ignore_ascii_case("t")
+}
+
+struct Compiler<'a> {
+    lines: Box<dyn Iterator<Item = (usize, &'a str)> + 'a>,
+    constants: HashMap<&'a str, Coordinate>,
+    macros: HashMap<&'a str, Vec<Instruction>>,
+}
+
+impl<'a> Compiler<'a> {
+    fn define(&self, line: usize, name: &'a str) -> Result<(), ScriptError> {
+        let kind = if !is_name(name) || is_keyword(name) {
+            ScriptErrorKind::InvalidName(name.to_string())
+        } else if self.constants.contains_key(name) || self.macros.contains_key(name) {
+            ScriptErrorKind::Redefined(name.to_string())
+        } else {
+            return Ok(());
+        };
+        Err(ScriptError { line, kind })
+    }
+
+    fn value(&self, line: usize, token: &str) -> Result<Coordinate, ScriptError> {
+        if let Some(&value) = self.constants.get(token) {
+            return Ok(value);
+        }
+        token.parse().map_err(|_| ScriptError {
+            line,
+            kind: if is_name(token) {
+                ScriptErrorKind::UnknownName(token.to_string())
+            } else {
+                ScriptErrorKind::InvalidCount(token.to_string())
+            },
+        })
+    }
+
+    fn instruction(&self, line: usize, text: &str) -> Result<Instruction, ScriptError> {
+        // A turn's argument is a direction, never a constant.
+        let substituted = match text.split_whitespace().collect::<Vec<_>>()[..] {
+            [command, argument] if !is_turn(command) => match self.constants.get(argument) {
+                Some(value) => format!("{} {}", command, value),
+                None => text.to_string(),
+            },
+            _ => text.to_string(),
+        };
+
+        substituted.parse().map_err(|error| ScriptError {
+            line,
+            kind: match error {
+                ParseInstructionError::InvalidArgument(command, argument)
+                    if is_name(&argument) && !is_turn(&command) =>
+                {
+                    ScriptErrorKind::UnknownName(argument)
+                }
+                error => ScriptErrorKind::Instruction(error),
+            },
+        })
+    }
+
+    /// Appends `count` copies of `body`, unless that would take the script past
+    /// `MAX_INSTRUCTIONS`.
+    fn expand(
+        line: usize,
+        instructions: &mut Vec<Instruction>,
+        body: &[Instruction],
+        count: usize,
+    ) -> Result<(), ScriptError> {
+        let length = body
+            .len()
+            .checked_mul(count)
+            .and_then(|length| length.checked_add(instructions.len()));
+        if length.is_none_or(|length| length > MAX_INSTRUCTIONS) {
+            return Err(ScriptError {
+                line,
+                kind: ScriptErrorKind::TooLong,
+            });
+        }
+        for _ in 0..count {
+            instructions.extend_from_slice(body);
+        }
+        Ok(())
+    }
+
+    fn block(&mut self, opened: Option<usize>) -> Result<Vec<Instruction>, ScriptError> {
+        let mut instructions = vec![];
+
+        while let Some((line, text)) = self.lines.next() {
+            match text.split_whitespace().collect::<Vec<_>>()[..] {
+                ["}"] => {
+                    return match opened {
+                        Some(_) => Ok(instructions),
+                        None => Err(ScriptError {
+                            line,
+                            kind: ScriptErrorKind::UnexpectedClose,
+                        }),
+                    }
+                }
+                ["let", name, "=", value] => {
+                    self.define(line, name)?;
+                    let value = self.value(line, value)?;
+                    self.constants.insert(name, value);
+                }
+                ["macro", name, "{"] => {
+                    self.define(line, name)?;
+                    let body = self.block(Some(line))?;
+                    self.macros.insert(name, body);
+                }
+                ["repeat", count, "{"] => {
+                    let count = self.value(line, count)?;
+                    let body = self.block(Some(line))?;
+                    let count = usize::try_from(count).map_err(|_| ScriptError {
+                        line,
+                        kind: ScriptErrorKind::InvalidCount(count.to_string()),
+                    })?;
+                    Self::expand(line, &mut instructions, &body, count)?;
+                }
+                [name] if self.macros.contains_key(name) => {
+                    Self::expand(line, &mut instructions, &self.macros[name], 1)?;
+                }
+                ["let", ..] | ["macro", ..] | ["repeat", ..] => {
+                    return Err(ScriptError {
+                        line,
+                        kind: ScriptErrorKind::InvalidStatement(text.to_string()),
+                    })
+                }
+                _ => {
+                    let instruction = self.instruction(line, text)?;
+                    Self::expand(line, &mut instructions, &[instruction], 1)?;
+                }
+            }
+        }
+
+        match opened {
+            Some(line) => Err(ScriptError {
+                line,
+                kind: ScriptErrorKind::UnclosedBlock,
+            }),
+            None => Ok(instructions),
+        }
+    }
+}
+
+/// Compiles a dive script into the plain instruction stream.
+///
+/// Besides one instruction per line, a script may contain:
+///
+/// - `let NAME = VALUE` to define a constant usable as an instruction argument or repeat count,
+/// - `macro NAME {` ... `}` to define a block that is expanded wherever `NAME` appears alone,
+/// - `repeat COUNT {` ... `}` to expand a block `COUNT` times.
+///
+/// A script may expand to at most `MAX_INSTRUCTIONS` instructions.
+///
+/// Names must be defined before they are used, cannot be redefined and cannot be a command,
+/// direction or statement word. Blank lines and `#` comments are ignored, and errors report the
+/// 1-based line they occur on.
+pub fn compile(source: &str) -> Result<Vec<Instruction>, ScriptError> {
+    let lines = source.lines().enumerate().filter_map(|(index, line)| {
+        let line = line.split('#').next().unwrap().trim();
+        if !line.is_empty() {
+            Some((index + 1, line))
+        } else {
+            None
+        }
+    });
+
+    Compiler {
+        lines: Box::new(lines),
+        constants: HashMap::new(),
+        macros: HashMap::new(),
+    }
+    .block(None)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{calculate_position, parse_input, Model, Turn};
+
+    #[test]
+    fn plain_instructions() {
+        let input = "
+            forward 5
+            down 5
+            forward 8
+            up 3
+            down 8
+            forward 2
+        ";
+
+        assert_eq!(
+            compile(input).unwrap(),
+            parse_input(input).collect::<Result<Vec<_>, _>>().unwrap()
+        );
+    }
+
+    #[test]
+    fn repeat_constants_and_macros() {
+        let input = "
+            let step = 3
+            let laps = 2
+
+            macro dive {
+                down step   # steepen
+                forward 1
+            }
+
+            repeat laps {
+                dive
+                repeat 2 {
+                    turn left
+                }
+            }
+            forward step
+        ";
+
+        let instructions = compile(input).unwrap();
+
+        assert_eq!(
+            instructions,
+            vec![
+                Instruction::Down(3),
+                Instruction::Forward(1),
+                Instruction::Turn(Turn::Left),
+                Instruction::Turn(Turn::Left),
+                Instruction::Down(3),
+                Instruction::Forward(1),
+                Instruction::Turn(Turn::Left),
+                Instruction::Turn(Turn::Left),
+                Instruction::Forward(3),
+            ]
+        );
+
+        let submarine = calculate_position(Model::Aim, &mut instructions.into_iter()).unwrap();
+        assert_eq!((submarine.x, submarine.depth), (3, 27));
+    }
+
+    #[test]
+    fn turn_ignores_constants() {
+        let source = "let l = 3\nlet r = 2\nturn l\nT R\nforward l\n";
+
+        assert_eq!(
+            compile(source),
+            Ok(vec![
+                Instruction::Turn(Turn::Left),
+                Instruction::Turn(Turn::Right),
+                Instruction::Forward(3),
+            ])
+        );
+    }
+
+    #[test]
+    fn errors_report_lines() {
+        let cases = vec![
+            (
+                "forward 1\nrepeat 2 {\n  down 1\n",
+                2,
+                ScriptErrorKind::UnclosedBlock,
+            ),
+            ("forward 1\n}\n", 2, ScriptErrorKind::UnexpectedClose),
+            (
+                "forward 1\n\nforward depth\n",
+                3,
+                ScriptErrorKind::UnknownName("depth".to_string()),
+            ),
+            (
+                "repeat times {\n}\n",
+                1,
+                ScriptErrorKind::UnknownName("times".to_string()),
+            ),
+            (
+                "repeat -1 {\n}\n",
+                1,
+                ScriptErrorKind::InvalidCount("-1".to_string()),
+            ),
+            (
+                "let a = 1\nlet a = 2\n",
+                2,
+                ScriptErrorKind::Redefined("a".to_string()),
+            ),
+            (
+                "let 9a = 1\n",
+                1,
+                ScriptErrorKind::InvalidName("9a".to_string()),
+            ),
+            (
+                "let Left = 3\n",
+                1,
+                ScriptErrorKind::InvalidName("Left".to_string()),
+            ),
+            (
+                "macro forward {\n}\n",
+                1,
+                ScriptErrorKind::InvalidName("forward".to_string()),
+            ),
+            (
+                "macro m {\n  m\n}\n",
+                2,
+                ScriptErrorKind::Instruction(ParseInstructionError::MissingArgument(
+                    "m".to_string(),
+                )),
+            ),
+            (
+                "let x 5\n",
+                1,
+                ScriptErrorKind::InvalidStatement("let x 5".to_string()),
+            ),
+            (
+                "# header\nforward 1\nswim 3\n",
+                3,
+                ScriptErrorKind::Instruction(ParseInstructionError::UnknownCommand(
+                    "swim".to_string(),
+                )),
+            ),
+        ];
+
+        for (input, line, kind) in cases {
+            assert_eq!(compile(input), Err(ScriptError { line, kind }), "{}", input);
+        }
+    }
+
+    #[test]
+    fn expansion_limit() {
+        let nested = "repeat 1024 {\n  repeat 1024 {\n    forward 1\n  }\n}\n";
+        assert_eq!(compile(nested).unwrap().len(), MAX_INSTRUCTIONS);
+
+        let cases = vec![
+            ("repeat 1024 {\n  repeat 1024 {\n    forward 1\n  }\n  up 1\n}\n", 1),
+            (
+                "repeat 9223372036854775807 {\n  repeat 9223372036854775807 {\n    forward 1\n  }\n}\n",
+                2,
+            ),
+            (
+                "macro lap {\n  repeat 1048576 {\n    forward 1\n  }\n}\nlap\nlap\n",
+                7,
+            ),
+            (
+                "repeat 1048576 {\n  forward 1\n}\nforward 1\n",
+                4,
+            ),
+        ];
+
+        for (input, line) in cases {
+            assert_eq!(
+                compile(input),
+                Err(ScriptError {
+                    line,
+                    kind: ScriptErrorKind::TooLong,
+                }),
+                "{}",
+                input
+            );
+        }
+        assert_eq!(
+            compile("repeat 1048577 {\n  forward 1\n}\n")
+                .unwrap_err()
+                .to_string(),
+            "line 1: script expands to more than 1048576 instructions"
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use std::fmt;
+
+use crate::{trajectory::Segment, Coordinate, Instruction, Model, OverflowError, Submarine};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum HazardKind {
+    /// The submarine is at or below the seabed.
+    Collision,
+    /// The submarine is above the seabed by less than the safety margin.
+    Margin,
+    /// No sonar reading covers this horizontal position.
+    Uncharted,
+}
+
+/// A run of consecutive units of one instruction with the same kind of hazard.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Hazard {
+    /// Index of the instruction during which the hazard occurs.
+    pub index: usize,
+    /// The state after the first unit of the run.
+    pub state: Submarine,
+    /// The state after the last unit of the run.
+    pub last: Submarine,
+    pub units: u64,
+    /// The reading under `state`.
+    pub seabed: Option<Coordinate>,
+    pub kind: HazardKind,
+}
+
+impl fmt::Display for Hazard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{} at x={} depth={}: ",
+            self.index, self.state.x, self.state.depth
+        )?;
+        match (self.kind, self.seabed) {
+            (HazardKind::Collision, Some(seabed)) => write!(f, "hits the seabed at {}", seabed),
+            (HazardKind::Margin, Some(seabed)) => {
+                write!(f, "only {} above the seabed", seabed - self.state.depth)
+            }
+            _ => write!(f, "no sonar reading"),
+        }?;
+        if self.units > 1 {
+            write!(
+                f,
+                ", for {} units to x={} depth={}",
+                self.units, self.last.x, self.last.depth
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn reading(seabed: &[Coordinate], x: Coordinate) -> Option<Coordinate> {
+    usize::try_from(x).ok().and_then(|x| seabed.get(x)).copied()
+}
+
+fn kind(reading: Option<Coordinate>, depth: Coordinate, margin: Coordinate) -> Option<HazardKind> {
+    match reading {
+        None => Some(HazardKind::Uncharted),
+        Some(floor) if depth >= floor => Some(HazardKind::Collision),
+        Some(floor) if (floor as i128 - depth as i128) < margin as i128 => Some(HazardKind::Margin),
+        Some(_) => None,
+    }
+}
+
+/// The runs of units of `segment` in each kind of hazard, as the first and last unit counting
+/// from one, in order and with neighbouring runs of the same kind merged.
+///
+/// Off the chart every unit is uncharted. Over the chart a segment that moves along `x` visits
+/// each reading at most once, so its units are checked one by one, while one that stays at the
+/// same `x` only changes depth and is split at the seabed and the margin.
+fn runs(
+    segment: &Segment,
+    seabed: &[Coordinate],
+    margin: Coordinate,
+) -> Vec<(u64, u64, HazardKind)> {
+    let charted = Coordinate::try_from(seabed.len()).unwrap_or(Coordinate::MAX);
+    let mut runs: Vec<_> = [
+        segment.units_at_x(Coordinate::MIN, -1),
+        segment.units_at_x(charted, Coordinate::MAX),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|(first, last)| (first, last, HazardKind::Uncharted))
+    .collect();
+
+    if let Some((first, last)) = segment.units_at_x(0, charted - 1) {
+        if segment.state(first).x == segment.state(last).x {
+            let floor = seabed[segment.state(first).x as usize];
+            runs.extend(
+                segment
+                    .units_at_depth(floor, Coordinate::MAX)
+                    .map(|(first, last)| (first, last, HazardKind::Collision)),
+            );
+            if margin > 0 {
+                let shallowest = (floor as i128 - margin as i128 + 1).max(Coordinate::MIN as i128);
+                runs.extend(
+                    floor
+                        .checked_sub(1)
+                        .and_then(|deepest| {
+                            segment.units_at_depth(shallowest as Coordinate, deepest)
+                        })
+                        .map(|(first, last)| (first, last, HazardKind::Margin)),
+                );
+            }
+        } else {
+            for unit in first..=last {
+                let state = segment.state(unit);
+                if let Some(kind) = kind(reading(seabed, state.x), state.depth, margin) {
+                    runs.push((unit, unit, kind));
+                }
+            }
+        }
+    }
+
+    runs.sort_unstable_by_key(|&(first, _, _)| first);
+    let mut merged: Vec<(u64, u64, HazardKind)> = vec![];
+    for (first, last, kind) in runs {
+        match merged.last_mut() {
+            Some(previous) if previous.2 == kind && previous.1 + 1 == first => previous.1 = last,
+            _ => merged.push((first, last, kind)),
+        }
+    }
+    merged
+}
+
+/// Replays `instructions` under `model`, comparing the depth after each unit of movement at
+/// horizontal position `x` with `seabed[x]`, and reports every run of units that collides with
+/// the seabed, comes within `margin` of it, or has no reading.
+pub fn check(
+    model: Model,
+    instructions: &[Instruction],
+    seabed: &[Coordinate],
+    margin: Coordinate,
+) -> Result<Vec<Hazard>, OverflowError> {
+    let mut hazards = vec![];
+    let mut state = Submarine::default();
+
+    for (index, instruction) in instructions.iter().enumerate() {
+        let segment = Segment::new(model, state, *instruction).ok_or(OverflowError {
+            index,
+            instruction: *instruction,
+        })?;
+        for (first, last, kind) in runs(&segment, seabed, margin) {
+            let start = segment.state(first);
+            hazards.push(Hazard {
+                index,
+                state: start,
+                last: segment.state(last),
+                units: last - first + 1,
+                seabed: reading(seabed, start.x),
+                kind,
+            });
+        }
+        state = segment.end;
+    }
+
+    Ok(hazards)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sweep() -> Vec<Coordinate> {
+        vec![199, 200, 208, 210, 200, 207, 240, 269, 260, 263]
+    }
+
+    #[test]
+    fn example_clear_dive() {
+        let input = vec![
+            Instruction::Forward(2),
+            Instruction::Down(150),
+            Instruction::Forward(7),
+        ];
+
+        assert_eq!(check(Model::Direct, &input, &sweep(), 10), Ok(vec![]));
+    }
+
+    #[test]
+    fn example_collisions_and_margin() {
+        let input = vec![
+            Instruction::Forward(4),
+            Instruction::Down(195),
+            Instruction::Forward(2),
+        ];
+
+        let hazards = check(Model::Direct, &input, &sweep(), 10).unwrap();
+
+        assert_eq!(hazards.len(), 1);
+        assert_eq!(
+            (hazards[0].index, hazards[0].kind, hazards[0].units),
+            (1, HazardKind::Margin, 5)
+        );
+        assert_eq!(
+            hazards[0].to_string(),
+            "#1 at x=4 depth=191: only 9 above the seabed, for 5 units to x=4 depth=195"
+        );
+
+        let input = vec![Instruction::Down(1), Instruction::Forward(5)];
+        let hazards = check(Model::Aim, &input, &sweep(), 0).unwrap();
+        assert_eq!(hazards, vec![]);
+
+        let input = vec![Instruction::Down(50), Instruction::Forward(5)];
+        let hazards = check(Model::Aim, &input, &sweep(), 0).unwrap();
+        assert_eq!(hazards.len(), 1);
+        assert_eq!(hazards[0].kind, HazardKind::Collision);
+        assert_eq!(
+            hazards[0].to_string(),
+            "#1 at x=4 depth=200: hits the seabed at 200, for 2 units to x=5 depth=250"
+        );
+    }
+
+    #[test]
+    fn runs_split_by_kind() {
+        let input = vec![
+            Instruction::Forward(4),
+            Instruction::Down(205),
+            Instruction::Up(205),
+            Instruction::Forward(1),
+        ];
+
+        let hazards: Vec<_> = check(Model::Direct, &input, &sweep(), 10)
+            .unwrap()
+            .into_iter()
+            .map(|hazard| {
+                (
+                    hazard.index,
+                    hazard.state.depth,
+                    hazard.last.depth,
+                    hazard.kind,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            hazards,
+            vec![
+                (1, 191, 199, HazardKind::Margin),
+                (1, 200, 205, HazardKind::Collision),
+                (2, 204, 200, HazardKind::Collision),
+                (2, 199, 191, HazardKind::Margin),
+            ]
+        );
+    }
+
+    #[test]
+    fn long_moves() {
+        let input = vec![
+            Instruction::Down(1),
+            Instruction::Forward(1_000_000_000_000),
+            Instruction::Back(2_000_000_000_000),
+        ];
+
+        let hazards = check(Model::Direct, &input, &sweep(), 10).unwrap();
+
+        assert_eq!(
+            hazards
+                .iter()
+                .map(|hazard| (hazard.index, hazard.state.x, hazard.units))
+                .collect::<Vec<_>>(),
+            vec![
+                (1, 10, 1_000_000_000_000 - 9),
+                (2, 999_999_999_999, 1_000_000_000_000 - 10),
+                (2, -1, 1_000_000_000_000),
+            ]
+        );
+        assert!(hazards
+            .iter()
+            .all(|hazard| hazard.kind == HazardKind::Uncharted));
+    }
+
+    #[test]
+    fn uncharted_positions() {
+        let input = vec![Instruction::Back(1), Instruction::Forward(11)];
+
+        let hazards = check(Model::Direct, &input, &sweep(), 0).unwrap();
+
+        assert_eq!(
+            hazards
+                .iter()
+                .map(|hazard| (hazard.index, hazard.state.x))
+                .collect::<Vec<_>>(),
+            vec![(0, -1), (1, 10)]
+        );
+        assert!(hazards
+            .iter()
+            .all(|hazard| hazard.kind == HazardKind::Uncharted));
+        assert_eq!(
+            hazards[0].to_string(),
+            "#0 at x=-1 depth=0: no sonar reading"
+        );
+    }
+}
@@ -0,0 +1,400 @@
+use std::{collections::BTreeMap, fmt};
+
+use crate::{Coordinate, Instruction, Model, OverflowError, Submarine};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Step {
+    pub index: usize,
+    pub instruction: Instruction,
+    pub state: Submarine,
+}
+
+pub struct Trajectory<'a> {
+    model: Model,
+    instructions: &'a mut dyn Iterator<Item = Instruction>,
+    state: Submarine,
+    index: usize,
+    overflowed: bool,
+}
+
+impl<'a> Trajectory<'a> {
+    pub fn new(model: Model, instructions: &'a mut dyn Iterator<Item = Instruction>) -> Self {
+        Trajectory {
+            model,
+            instructions,
+            state: Submarine::default(),
+            index: 0,
+            overflowed: false,
+        }
+    }
+}
+
+/// Yields each step in turn, ending after the first instruction that overflows.
+impl<'a> Iterator for Trajectory<'a> {
+    type Item = Result<Step, OverflowError>;
+
+    fn next(&mut self) -> Option<Result<Step, OverflowError>> {
+        if self.overflowed {
+            return None;
+        }
+        let instruction = self.instructions.next()?;
+        self.state = match self.state.step(self.model, &instruction) {
+            Some(state) => state,
+            None => {
+                self.overflowed = true;
+                return Some(Err(OverflowError {
+                    index: self.index,
+                    instruction,
+                }));
+            }
+        };
+        let step = Step {
+            index: self.index,
+            instruction,
+            state: self.state,
+        };
+        self.index += 1;
+        Some(Ok(step))
+    }
+}
+
+/// An instruction's movement split into `units` equal steps, along which the submarine travels
+/// in a straight line from `start` to `end`.
+///
+/// Instructions that do not move the submarine take a single unit, reaching `end` at once.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Segment {
+    pub start: Submarine,
+    pub end: Submarine,
+    pub units: u64,
+}
+
+impl Segment {
+    /// The segment `instruction` takes from `start`, or `None` if it overflows.
+    pub fn new(model: Model, start: Submarine, instruction: Instruction) -> Option<Segment> {
+        let end = start.step(model, &instruction)?;
+        let units = match (model, instruction) {
+            (_, Instruction::Forward(distance))
+            | (_, Instruction::Back(distance))
+            | (_, Instruction::Strafe(distance))
+            | (Model::Direct, Instruction::Down(distance))
+            | (Model::Direct, Instruction::Up(distance)) => distance.unsigned_abs().max(1),
+            _ => 1,
+        };
+        Some(Segment { start, end, units })
+    }
+
+    /// The change in `x`, `y` and depth over each unit.
+    fn per_unit(&self) -> (i128, i128, i128) {
+        let change =
+            |start: Coordinate, end: Coordinate| (end as i128 - start as i128) / self.units as i128;
+        (
+            change(self.start.x, self.end.x),
+            change(self.start.y, self.end.y),
+            change(self.start.depth, self.end.depth),
+        )
+    }
+
+    /// Manhattan distance over `x`, `y` and depth covered by each unit.
+    pub fn unit_distance(&self) -> u128 {
+        let (dx, dy, depth) = self.per_unit();
+        dx.unsigned_abs() + dy.unsigned_abs() + depth.unsigned_abs()
+    }
+
+    /// The state after `unit` units, counting from one.
+    pub fn state(&self, unit: u64) -> Submarine {
+        let (dx, dy, depth) = self.per_unit();
+        // Every unit lies between `start` and `end`, so the results are in range.
+        let along =
+            |start: Coordinate, change: i128| (start as i128 + change * unit as i128) as Coordinate;
+        Submarine {
+            x: along(self.start.x, dx),
+            y: along(self.start.y, dy),
+            depth: along(self.start.depth, depth),
+            ..self.end
+        }
+    }
+
+    /// The state after each unit in turn.
+    pub fn states(&self) -> impl Iterator<Item = Submarine> + '_ {
+        (1..=self.units).map(|unit| self.state(unit))
+    }
+
+    /// The first and last unit, counting from one, after which a coordinate starting at `start`
+    /// and moving `change` per unit lies within `low..=high`. The coordinate changes steadily, so
+    /// every unit in between does too.
+    fn units_between(
+        &self,
+        start: Coordinate,
+        change: i128,
+        low: Coordinate,
+        high: Coordinate,
+    ) -> Option<(u64, u64)> {
+        let (start, low, high) = (start as i128, low as i128, high as i128);
+        let ceil = |numerator: i128, denominator: i128| -(-numerator).div_euclid(denominator);
+        let (first, last) = match change {
+            0 if (low..=high).contains(&start) => (1, self.units as i128),
+            0 => return None,
+            change if change > 0 => (ceil(low - start, change), (high - start).div_euclid(change)),
+            change => (
+                ceil(start - high, -change),
+                (start - low).div_euclid(-change),
+            ),
+        };
+        let (first, last) = (first.max(1), last.min(self.units as i128));
+        (first <= last).then_some((first as u64, last as u64))
+    }
+
+    /// The first and last unit, counting from one, after which the depth lies within
+    /// `low..=high`, with every unit in between there too.
+    pub fn units_at_depth(&self, low: Coordinate, high: Coordinate) -> Option<(u64, u64)> {
+        let (_, _, change) = self.per_unit();
+        self.units_between(self.start.depth, change, low, high)
+    }
+
+    /// The first and last unit, counting from one, after which `x` lies within `low..=high`,
+    /// with every unit in between there too.
+    pub fn units_at_x(&self, low: Coordinate, high: Coordinate) -> Option<(u64, u64)> {
+        let (change, _, _) = self.per_unit();
+        self.units_between(self.start.x, change, low, high)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DiveStatistics {
+    pub final_state: Submarine,
+    pub max_depth: Coordinate,
+    /// Manhattan distance over `x`, `y` and depth, summed over every unit of movement.
+    pub distance: Coordinate,
+    /// Units of movement spent in each depth band, keyed by the band's shallowest depth.
+    pub time_at_depth: BTreeMap<Coordinate, Coordinate>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AnalysisError {
+    InvalidBand(Coordinate),
+    Overflow(OverflowError),
+}
+
+impl fmt::Display for AnalysisError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnalysisError::InvalidBand(band) => {
+                write!(f, "depth band {} must be positive", band)
+            }
+            AnalysisError::Overflow(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for AnalysisError {}
+
+/// The units of `segment` spent in each depth band it passes through, keyed by the band's
+/// shallowest depth, or `None` if a key is out of range.
+///
+/// Shallow segments are counted a band at a time and steep ones a unit at a time, whichever is
+/// fewer, so this never does more work than there are entries in the result.
+fn bands(segment: &Segment, band: Coordinate) -> Option<Vec<(Coordinate, u64)>> {
+    let key = |depth: Coordinate| depth.checked_sub(depth.rem_euclid(band));
+    let (first, last) = (segment.state(1).depth, segment.end.depth);
+    let (shallowest, deepest) = (key(first.min(last))?, key(first.max(last))?);
+
+    let crossed = (deepest as i128 - shallowest as i128) / band as i128 + 1;
+    if crossed > segment.units as i128 {
+        return segment
+            .states()
+            .map(|state| Some((key(state.depth)?, 1)))
+            .collect();
+    }
+
+    let mut counts = vec![];
+    let mut low = shallowest;
+    loop {
+        let high = low.saturating_add(band - 1);
+        if let Some((first, last)) = segment.units_at_depth(low, high) {
+            counts.push((low, last - first + 1));
+        }
+        match low.checked_add(band) {
+            Some(next) if next <= deepest => low = next,
+            _ => return Some(counts),
+        }
+    }
+}
+
+/// Replays `instructions`, grouping depths into bands `band` units deep.
+pub fn analyse(
+    model: Model,
+    instructions: &mut dyn Iterator<Item = Instruction>,
+    band: Coordinate,
+) -> Result<DiveStatistics, AnalysisError> {
+    if band <= 0 {
+        return Err(AnalysisError::InvalidBand(band));
+    }
+
+    let mut statistics = DiveStatistics {
+        final_state: Submarine::default(),
+        max_depth: 0,
+        distance: 0,
+        time_at_depth: BTreeMap::new(),
+    };
+
+    for (index, instruction) in instructions.enumerate() {
+        let overflow = AnalysisError::Overflow(OverflowError { index, instruction });
+        let segment = Segment::new(model, statistics.final_state, instruction).ok_or(overflow)?;
+
+        let moved = Coordinate::try_from(segment.unit_distance() * segment.units as u128)
+            .map_err(|_| overflow)?;
+        if moved > 0 {
+            statistics.distance = statistics.distance.checked_add(moved).ok_or(overflow)?;
+            for (key, units) in bands(&segment, band).ok_or(overflow)? {
+                let total = statistics.time_at_depth.entry(key).or_insert(0);
+                *total = Coordinate::try_from(units)
+                    .ok()
+                    .and_then(|units| total.checked_add(units))
+                    .ok_or(overflow)?;
+            }
+        }
+        statistics.max_depth = statistics
+            .max_depth
+            .max(segment.state(1).depth)
+            .max(segment.end.depth);
+        statistics.final_state = segment.end;
+    }
+
+    Ok(statistics)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::example_instructions;
+
+    #[test]
+    fn example_trajectory() {
+        let steps: Vec<_> = Trajectory::new(Model::Aim, &mut example_instructions().into_iter())
+            .map(Result::unwrap)
+            .map(|step| (step.index, step.instruction, step.state.x, step.state.depth))
+            .collect();
+
+        assert_eq!(
+            steps,
+            vec![
+                (0, Instruction::Forward(5), 5, 0),
+                (1, Instruction::Down(5), 5, 0),
+                (2, Instruction::Forward(8), 13, 40),
+                (3, Instruction::Up(3), 13, 40),
+                (4, Instruction::Down(8), 13, 40),
+                (5, Instruction::Forward(2), 15, 60),
+            ]
+        );
+    }
+
+    #[test]
+    fn segment_units() {
+        let start = Submarine {
+            aim: 2,
+            ..Submarine::default()
+        };
+
+        let segment = Segment::new(Model::Aim, start, Instruction::Back(3)).unwrap();
+        let depths: Vec<_> = segment
+            .states()
+            .map(|state| (state.x, state.depth))
+            .collect();
+        assert_eq!(depths, vec![(-1, -2), (-2, -4), (-3, -6)]);
+        assert_eq!(segment.unit_distance(), 3);
+        assert_eq!(segment.units_at_depth(-5, -3), Some((2, 2)));
+        assert_eq!(segment.units_at_depth(-100, -4), Some((2, 3)));
+        assert_eq!(segment.units_at_depth(0, 10), None);
+        assert_eq!(segment.units_at_x(-2, 5), Some((1, 2)));
+
+        let segment = Segment::new(Model::Aim, start, Instruction::Down(3)).unwrap();
+        let aims: Vec<_> = segment.states().map(|state| state.aim).collect();
+        assert_eq!(aims, vec![5]);
+        assert_eq!(segment.units_at_depth(0, 0), Some((1, 1)));
+    }
+
+    #[test]
+    fn long_moves() {
+        let input = vec![
+            Instruction::Down(3),
+            Instruction::Forward(200_000_000),
+            Instruction::Up(3),
+            Instruction::Forward(1_000_000_000_000),
+        ];
+
+        let statistics = analyse(Model::Aim, &mut input.clone().into_iter(), 1_000_000).unwrap();
+        assert_eq!(statistics.max_depth, 600_000_000);
+        assert_eq!(statistics.distance, 4 * 200_000_000 + 1_000_000_000_000);
+        assert_eq!(statistics.time_at_depth.len(), 601);
+        assert_eq!(statistics.time_at_depth[&0], 333_333);
+        assert_eq!(statistics.time_at_depth[&600_000_000], 1_000_000_000_001);
+
+        let statistics = analyse(Model::Direct, &mut input.into_iter(), 2).unwrap();
+        assert_eq!(statistics.distance, 6 + 200_000_000 + 1_000_000_000_000);
+        assert_eq!(
+            statistics.time_at_depth,
+            vec![(0, 1_000_000_000_003), (2, 200_000_003)]
+                .into_iter()
+                .collect(),
+        );
+    }
+
+    #[test]
+    fn example_analyse_direct() {
+        let statistics =
+            analyse(Model::Direct, &mut example_instructions().into_iter(), 5).unwrap();
+
+        assert_eq!(statistics.max_depth, 10);
+        assert_eq!(statistics.distance, 15 + 5 + 3 + 8);
+        assert_eq!(
+            statistics.time_at_depth,
+            vec![(0, 14), (5, 14), (10, 3)].into_iter().collect(),
+        );
+    }
+
+    #[test]
+    fn example_analyse_aim() {
+        let statistics = analyse(Model::Aim, &mut example_instructions().into_iter(), 25).unwrap();
+
+        assert_eq!(statistics.final_state.depth, 60);
+        assert_eq!(statistics.max_depth, 60);
+        assert_eq!(statistics.distance, 15 + 60);
+        assert_eq!(
+            statistics.time_at_depth,
+            vec![(0, 9), (25, 4), (50, 2)].into_iter().collect(),
+        );
+    }
+
+    #[test]
+    fn band_must_be_positive() {
+        for band in [0, -5] {
+            assert_eq!(
+                analyse(Model::Direct, &mut example_instructions().into_iter(), band),
+                Err(AnalysisError::InvalidBand(band))
+            );
+        }
+    }
+
+    #[test]
+    fn overflow_ends_trajectory() {
+        let input = vec![
+            Instruction::Forward(1),
+            Instruction::Forward(Coordinate::MAX),
+            Instruction::Forward(1),
+        ];
+
+        let steps: Vec<_> = Trajectory::new(Model::Direct, &mut input.clone().into_iter())
+            .map(|step| step.map(|step| step.state.x))
+            .collect();
+        let overflow = OverflowError {
+            index: 1,
+            instruction: Instruction::Forward(Coordinate::MAX),
+        };
+        assert_eq!(steps, vec![Ok(1), Err(overflow)]);
+        assert_eq!(
+            analyse(Model::Direct, &mut input.into_iter(), 5),
+            Err(AnalysisError::Overflow(overflow))
+        );
+    }
+}
@@ -0,0 +1,278 @@
+use std::fmt;
+
+use crate::{Coordinate, Instruction, Model, OverflowError, Submarine};
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct Constraints {
+    pub max_depth: Option<Coordinate>,
+    /// Largest aim allowed in either direction.
+    pub max_aim: Option<Coordinate>,
+    /// Largest rise in depth per unit of horizontal travel.
+    pub max_ascent_rate: Option<Coordinate>,
+    /// Smallest horizontal distance between two `down` or `up` instructions.
+    pub min_depth_change_spacing: Option<Coordinate>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ViolationKind {
+    Depth(Coordinate),
+    Aim(Coordinate),
+    /// Rose by `ascent` over `distance` units of horizontal travel.
+    AscentRate {
+        ascent: Coordinate,
+        distance: Coordinate,
+    },
+    /// Changed depth only `distance` after the previous change.
+    DepthChangeSpacing(Coordinate),
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Violation {
+    pub index: usize,
+    pub instruction: Instruction,
+    pub before: Submarine,
+    pub after: Submarine,
+    pub kind: ViolationKind,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{} {}: ", self.index, self.instruction)?;
+        match self.kind {
+            ViolationKind::Depth(depth) => write!(f, "reaches depth {}", depth),
+            ViolationKind::Aim(aim) => write!(f, "has aim {}", aim),
+            ViolationKind::AscentRate { ascent, distance } => {
+                write!(f, "rises {} over {} horizontal units", ascent, distance)
+            }
+            ViolationKind::DepthChangeSpacing(distance) => write!(
+                f,
+                "changes depth {} horizontal units after the previous change",
+                distance
+            ),
+        }?;
+        write!(
+            f,
+            " at x={} y={} depth={} aim={}",
+            self.after.x, self.after.y, self.after.depth, self.after.aim
+        )
+    }
+}
+
+fn horizontal_distance(before: &Submarine, after: &Submarine) -> Option<Coordinate> {
+    (after.x.checked_sub(before.x)?.checked_abs()?)
+        .checked_add(after.y.checked_sub(before.y)?.checked_abs()?)
+}
+
+/// Replays `instructions` under `model`, reporting every constraint broken by the state each
+/// instruction leaves the submarine in.
+///
+/// In the direct model an `up` ascends over the horizontal distance travelled since the last
+/// depth change, while in the aim model the ascent happens during the travel itself.
+pub fn validate(
+    model: Model,
+    instructions: &[Instruction],
+    constraints: &Constraints,
+) -> Result<Vec<Violation>, OverflowError> {
+    let mut violations = vec![];
+    let mut state = Submarine::default();
+    let mut since_depth_change: Option<Coordinate> = None;
+
+    for (index, instruction) in instructions.iter().enumerate() {
+        let overflow = OverflowError {
+            index,
+            instruction: *instruction,
+        };
+        let before = state;
+        let after = state.step(model, instruction).ok_or(overflow)?;
+        let travelled = horizontal_distance(&before, &after).ok_or(overflow)?;
+        let mut report = |kind| {
+            violations.push(Violation {
+                index,
+                instruction: *instruction,
+                before,
+                after,
+                kind,
+            })
+        };
+
+        if let Some(max_depth) = constraints.max_depth {
+            if after.depth > max_depth {
+                report(ViolationKind::Depth(after.depth));
+            }
+        }
+
+        if let Some(max_aim) = constraints.max_aim {
+            // A negative limit is exceeded by every aim.
+            if u64::try_from(max_aim).map_or(true, |max_aim| after.aim.unsigned_abs() > max_aim) {
+                report(ViolationKind::Aim(after.aim));
+            }
+        }
+
+        let is_depth_change = matches!(instruction, Instruction::Down(_) | Instruction::Up(_));
+        if let Some(max_ascent_rate) = constraints.max_ascent_rate {
+            let ascent = before.depth.checked_sub(after.depth).ok_or(overflow)?;
+            let distance = match model {
+                Model::Direct => since_depth_change.unwrap_or(0),
+                Model::Aim => travelled,
+            };
+            // A limit too large to represent cannot be exceeded.
+            if max_ascent_rate
+                .checked_mul(distance)
+                .is_some_and(|limit| ascent > limit)
+            {
+                report(ViolationKind::AscentRate { ascent, distance });
+            }
+        }
+
+        if is_depth_change {
+            if let (Some(min_spacing), Some(distance)) =
+                (constraints.min_depth_change_spacing, since_depth_change)
+            {
+                if distance < min_spacing {
+                    report(ViolationKind::DepthChangeSpacing(distance));
+                }
+            }
+            since_depth_change = Some(0);
+        } else {
+            let distance = since_depth_change.unwrap_or(0).checked_add(travelled);
+            since_depth_change = Some(distance.ok_or(overflow)?);
+        }
+
+        state = after;
+    }
+
+    Ok(violations)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::example_instructions;
+
+    #[test]
+    fn example_is_valid_without_constraints() {
+        for model in [Model::Direct, Model::Aim] {
+            assert_eq!(
+                validate(model, &example_instructions(), &Constraints::default()),
+                Ok(vec![])
+            );
+        }
+    }
+
+    #[test]
+    fn example_direct() {
+        let constraints = Constraints {
+            max_depth: Some(8),
+            max_aim: None,
+            max_ascent_rate: Some(0),
+            min_depth_change_spacing: Some(5),
+        };
+
+        let violations: Vec<_> = validate(Model::Direct, &example_instructions(), &constraints)
+            .unwrap()
+            .into_iter()
+            .map(|violation| (violation.index, violation.kind))
+            .collect();
+
+        assert_eq!(
+            violations,
+            vec![
+                (
+                    3,
+                    ViolationKind::AscentRate {
+                        ascent: 3,
+                        distance: 8
+                    }
+                ),
+                (4, ViolationKind::Depth(10)),
+                (4, ViolationKind::DepthChangeSpacing(0)),
+                (5, ViolationKind::Depth(10)),
+            ]
+        );
+    }
+
+    #[test]
+    fn example_aim() {
+        let constraints = Constraints {
+            max_depth: Some(50),
+            max_aim: Some(8),
+            max_ascent_rate: None,
+            min_depth_change_spacing: Some(1),
+        };
+
+        let violations = validate(Model::Aim, &example_instructions(), &constraints).unwrap();
+
+        assert_eq!(
+            violations
+                .iter()
+                .map(|violation| (violation.index, violation.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                (4, ViolationKind::Aim(10)),
+                (4, ViolationKind::DepthChangeSpacing(0)),
+                (5, ViolationKind::Depth(60)),
+                (5, ViolationKind::Aim(10)),
+            ]
+        );
+        assert_eq!(violations[2].before.depth, 40);
+        assert_eq!(
+            violations[2].to_string(),
+            "#5 forward 2: reaches depth 60 at x=15 y=0 depth=60 aim=10"
+        );
+    }
+
+    #[test]
+    fn most_negative_aim() {
+        let constraints = Constraints {
+            max_aim: Some(Coordinate::MAX),
+            ..Constraints::default()
+        };
+
+        let violations = validate(
+            Model::Aim,
+            &[Instruction::Down(Coordinate::MIN)],
+            &constraints,
+        )
+        .unwrap();
+
+        assert_eq!(
+            violations
+                .iter()
+                .map(|violation| (violation.index, violation.kind))
+                .collect::<Vec<_>>(),
+            vec![(0, ViolationKind::Aim(Coordinate::MIN))]
+        );
+    }
+
+    #[test]
+    fn aim_ascent_rate() {
+        let input = vec![
+            Instruction::Down(2),
+            Instruction::Forward(10),
+            Instruction::Up(5),
+            Instruction::Forward(4),
+            Instruction::Down(4),
+            Instruction::Back(1),
+        ];
+        let constraints = Constraints {
+            max_ascent_rate: Some(2),
+            ..Constraints::default()
+        };
+
+        let violations = validate(Model::Aim, &input, &constraints).unwrap();
+
+        assert_eq!(
+            violations
+                .iter()
+                .map(|violation| (violation.index, violation.kind))
+                .collect::<Vec<_>>(),
+            vec![(
+                3,
+                ViolationKind::AscentRate {
+                    ascent: 12,
+                    distance: 4
+                }
+            ),]
+        );
+    }
+}
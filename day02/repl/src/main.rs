@@ -0,0 +1,306 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, Read, Write},
+};
+
+use day02_part1::{script::compile, *};
+
+#[derive(Debug, Eq, PartialEq)]
+enum Command {
+    Instruction(Instruction),
+    Undo,
+    Reset,
+    Save(String),
+    Load(String),
+    Help,
+    Quit,
+}
+
+fn parse_command(line: &str) -> Result<Command, ParseInstructionError> {
+    let mut tokens = line.split_whitespace();
+    match (tokens.next(), tokens.next(), tokens.next()) {
+        (Some("undo"), None, _) => Ok(Command::Undo),
+        (Some("reset"), None, _) => Ok(Command::Reset),
+        (Some("help"), None, _) => Ok(Command::Help),
+        (Some("quit"), None, _) | (Some("exit"), None, _) => Ok(Command::Quit),
+        (Some("save"), Some(path), None) => Ok(Command::Save(path.to_string())),
+        (Some("load"), Some(path), None) => Ok(Command::Load(path.to_string())),
+        _ => line.parse().map(Command::Instruction),
+    }
+}
+
+/// The state under the direct and aim models.
+type States = (Submarine, Submarine);
+
+/// How to undo a command.
+enum Change {
+    /// An instruction was appended.
+    Pushed,
+    /// The whole script was replaced; these are the script and states it replaced.
+    Replaced(Vec<Instruction>, Vec<States>),
+}
+
+struct Session {
+    instructions: Vec<Instruction>,
+    /// `states[i]` is the state after the first `i` instructions, so the last entry is current.
+    states: Vec<States>,
+    history: Vec<Change>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session {
+            instructions: vec![],
+            states: vec![(Submarine::default(), Submarine::default())],
+            history: vec![],
+        }
+    }
+}
+
+fn step(
+    (direct, aim): States,
+    index: usize,
+    instruction: Instruction,
+) -> Result<States, OverflowError> {
+    match (
+        direct.step(Model::Direct, &instruction),
+        aim.step(Model::Aim, &instruction),
+    ) {
+        (Some(direct), Some(aim)) => Ok((direct, aim)),
+        _ => Err(OverflowError { index, instruction }),
+    }
+}
+
+impl Session {
+    fn states(&self) -> States {
+        *self.states.last().unwrap()
+    }
+
+    /// Appends `instruction`, refusing one that overflows under either model.
+    fn push(&mut self, instruction: Instruction) -> Result<(), OverflowError> {
+        let next = step(self.states(), self.instructions.len(), instruction)?;
+        self.instructions.push(instruction);
+        self.states.push(next);
+        self.history.push(Change::Pushed);
+        Ok(())
+    }
+
+    /// Switches to `instructions`, refusing a script that overflows under either model.
+    fn replace(&mut self, instructions: Vec<Instruction>) -> Result<(), OverflowError> {
+        let mut states = Session::default().states;
+        for (index, &instruction) in instructions.iter().enumerate() {
+            states.push(step(*states.last().unwrap(), index, instruction)?);
+        }
+        let previous = (
+            std::mem::replace(&mut self.instructions, instructions),
+            std::mem::replace(&mut self.states, states),
+        );
+        self.history.push(Change::Replaced(previous.0, previous.1));
+        Ok(())
+    }
+
+    fn execute(&mut self, command: Command) -> Result<String, Box<dyn std::error::Error>> {
+        match command {
+            Command::Instruction(instruction) => self.push(instruction)?,
+            Command::Undo => match self.history.pop() {
+                Some(Change::Pushed) => {
+                    self.instructions.pop();
+                    self.states.pop();
+                }
+                Some(Change::Replaced(instructions, states)) => {
+                    self.instructions = instructions;
+                    self.states = states;
+                }
+                None => return Ok("Nothing to undo".to_string()),
+            },
+            Command::Reset => self.replace(vec![])?,
+            Command::Save(path) => {
+                let mut file = File::create(&path)?;
+                for instruction in &self.instructions {
+                    writeln!(file, "{}", instruction)?;
+                }
+                return Ok(format!(
+                    "Saved {} instructions to {}",
+                    self.instructions.len(),
+                    path
+                ));
+            }
+            Command::Load(path) => {
+                let mut contents = String::new();
+                File::open(&path)?.read_to_string(&mut contents)?;
+                self.replace(compile(&contents)?)?;
+            }
+            Command::Help => return Ok([
+                "Type an instruction such as `forward 5`, `down 2`, `turn left` or `strafe -3`,",
+                "or one of: undo, reset, save <path>, load <path>, help, quit",
+            ]
+            .join("\n")),
+            Command::Quit => return Ok(String::new()),
+        }
+
+        Ok(self.render())
+    }
+
+    fn render(&self) -> String {
+        let (direct, aim) = self.states();
+        let rows = [
+            ("x", direct.x.to_string(), aim.x.to_string()),
+            ("y", direct.y.to_string(), aim.y.to_string()),
+            ("depth", direct.depth.to_string(), aim.depth.to_string()),
+            ("aim", direct.aim.to_string(), aim.aim.to_string()),
+            (
+                "heading",
+                format!("{:?}", direct.heading),
+                format!("{:?}", aim.heading),
+            ),
+        ];
+
+        let mut table = format!(
+            "#{:<8} {:>12} {:>12}\n",
+            self.instructions.len(),
+            "direct",
+            "aim"
+        );
+        for (name, direct, aim) in rows {
+            table.push_str(&format!("{:<9} {:>12} {:>12}\n", name, direct, aim));
+        }
+        table
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut session = Session::default();
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    print!("{}", session.render());
+    loop {
+        print!("> ");
+        stdout.flush()?;
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            break;
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        match parse_command(line) {
+            Ok(Command::Quit) => break,
+            Ok(command) => match session.execute(command) {
+                Ok(output) => println!("{}", output.trim_end()),
+                Err(error) => println!("Error: {}", error),
+            },
+            Err(error) => println!("Error: {}", error),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn example_parse_command() {
+        assert_eq!(
+            parse_command("forward 5"),
+            Ok(Command::Instruction(Instruction::Forward(5)))
+        );
+        assert_eq!(parse_command("undo"), Ok(Command::Undo));
+        assert_eq!(
+            parse_command("load ./day02/part1/input.txt"),
+            Ok(Command::Load("./day02/part1/input.txt".to_string()))
+        );
+        assert_eq!(
+            parse_command("dive 5"),
+            Err(ParseInstructionError::UnknownCommand("dive".to_string()))
+        );
+        assert_eq!(
+            parse_command("down"),
+            Err(ParseInstructionError::MissingArgument("down".to_string()))
+        );
+    }
+
+    #[test]
+    fn example_session() {
+        let mut session = Session::default();
+        for line in [
+            "forward 5",
+            "down 5",
+            "forward 8",
+            "up 3",
+            "down 8",
+            "forward 2",
+        ] {
+            session.execute(parse_command(line).unwrap()).unwrap();
+        }
+
+        let (direct, aim) = session.states();
+        assert_eq!((direct.x, direct.depth), (15, 10));
+        assert_eq!((aim.x, aim.depth), (15, 60));
+
+        let output = session.execute(Command::Undo).unwrap();
+        assert!(output.starts_with("#5 "));
+        assert!(output.contains("depth               10           40\n"));
+
+        session.execute(Command::Reset).unwrap();
+        assert_eq!(session.states().0, Submarine::default());
+
+        session.execute(Command::Undo).unwrap();
+        assert_eq!(session.instructions.len(), 5);
+        assert_eq!(session.states.len(), 6);
+        assert_eq!(session.states().1.depth, 40);
+
+        for _ in 0..5 {
+            session.execute(Command::Undo).unwrap();
+        }
+        assert_eq!(session.states(), Session::default().states());
+        assert_eq!(
+            session.execute(Command::Undo).unwrap(),
+            "Nothing to undo".to_string()
+        );
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path = std::env::temp_dir().join(format!("day02_repl_{}.txt", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+
+        let mut session = Session::default();
+        session
+            .execute(Command::Instruction(Instruction::Forward(3)))
+            .unwrap();
+        session
+            .execute(Command::Instruction(Instruction::Turn(Turn::Left)))
+            .unwrap();
+        session.execute(Command::Save(path.clone())).unwrap();
+
+        let mut loaded = Session::default();
+        loaded.execute(Command::Load(path.clone())).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.instructions, session.instructions);
+        assert!(loaded.execute(Command::Load(path)).is_err());
+    }
+
+    #[test]
+    fn overflow_is_refused() {
+        let mut session = Session::default();
+        session
+            .execute(Command::Instruction(Instruction::Down(1)))
+            .unwrap();
+
+        let error = session
+            .execute(parse_command("up -9223372036854775807").unwrap())
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "instruction 1 (up -9223372036854775807) overflows"
+        );
+        assert_eq!(session.instructions, vec![Instruction::Down(1)]);
+    }
+}
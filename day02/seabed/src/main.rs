@@ -0,0 +1,77 @@
+use std::{fs::File, io::Read};
+
+use day01_part1::parse_input;
+use day02_part1::{
+    script::compile,
+    seabed::{check, HazardKind},
+    Coordinate, Model,
+};
+
+fn read(path: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let mut contents = String::new();
+    File::open(path)?.read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut arguments = std::env::args().skip(1);
+    let sonar = arguments
+        .next()
+        .unwrap_or_else(|| "./day01/part1/input.txt".to_string());
+    let script = arguments
+        .next()
+        .unwrap_or_else(|| "./day02/part1/input.txt".to_string());
+    let margin = match arguments.next() {
+        Some(margin) => margin.parse()?,
+        None => 0,
+    };
+    let model = match arguments.next() {
+        Some(model) => model.parse()?,
+        None => Model::Aim,
+    };
+
+    let seabed: Vec<Coordinate> = parse_input(&read(&sonar)?)?;
+    let instructions = compile(&read(&script)?)?;
+    let hazards = check(model, &instructions, &seabed, margin)?;
+
+    for hazard in &hazards {
+        println!("{}", hazard);
+    }
+
+    let count = |kind| {
+        hazards
+            .iter()
+            .filter(|hazard| hazard.kind == kind)
+            .map(|hazard| hazard.units)
+            .sum::<u64>()
+    };
+    println!(
+        "Collisions: {}, Within Margin: {}, Uncharted: {}",
+        count(HazardKind::Collision),
+        count(HazardKind::Margin),
+        count(HazardKind::Uncharted)
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use day02_part1::Instruction;
+
+    #[test]
+    fn example() {
+        let seabed =
+            parse_input::<Coordinate>("199\n200\n208\n210\n200\n207\n240\n269\n260\n263\n")
+                .unwrap();
+        let instructions = compile("forward 4\ndown 195\nforward 2\n").unwrap();
+
+        let hazards = check(Model::Direct, &instructions, &seabed, 10).unwrap();
+
+        assert_eq!(hazards.len(), 1);
+        assert_eq!(hazards[0].units, 5);
+        assert_eq!(hazards[0].index, 1);
+        assert_eq!(instructions[hazards[0].index], Instruction::Down(195));
+    }
+}
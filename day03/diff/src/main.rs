@@ -0,0 +1,34 @@
+use day03_part1::{cli::Options, diff::compare, encoding::decode, *};
+
+fn print_diff<W: Word>(
+    before: &[u8],
+    after: &[u8],
+    options: &Options,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (bits, before) = decode::<W>(before, options.encoding, options.order)?;
+    let (_, after) = decode::<W>(after, options.encoding, options.order)?;
+
+    print!("{}", compare(bits, &before, &after, TiePolicy::PreferOne)?);
+
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let options = Options::parse(std::env::args().skip(1), &[])?;
+    let [before, after] = &options.paths[..] else {
+        return Err("expected the paths of two reports, before and after".into());
+    };
+
+    let (before, after) = (std::fs::read(before)?, std::fs::read(after)?);
+    let bits = options.encoding.width(&before)?;
+    let after_bits = options.encoding.width(&after)?;
+    if bits != after_bits {
+        return Err(format!(
+            "the reports have {} and {} bits, so cannot be compared",
+            bits, after_bits
+        )
+        .into());
+    }
+
+    with_word!(Width::detect(bits), print_diff(&before, &after, &options))
+}
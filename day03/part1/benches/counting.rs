@@ -0,0 +1,41 @@
+use std::time::Instant;
+
+use day03_part1::{
+    counting::{count_ones, count_ones_sliced},
+    testing::xorshift,
+};
+
+const READINGS: usize = 1_000_000;
+
+fn readings() -> Vec<u64> {
+    xorshift(0x2545_f491_4f6c_dd1d).take(READINGS).collect()
+}
+
+fn main() {
+    let readings = readings();
+
+    for bits in [12, 64] {
+        let masked: Vec<u64> = readings
+            .iter()
+            .map(|&value| value & (u64::MAX >> (64 - bits)))
+            .collect();
+
+        let start = Instant::now();
+        let simple = count_ones(bits, &mut masked.iter().copied());
+        let simple_time = start.elapsed();
+
+        let start = Instant::now();
+        let sliced = count_ones_sliced(bits, &mut masked.iter().copied());
+        let sliced_time = start.elapsed();
+
+        assert_eq!(simple, sliced);
+        println!(
+            "{} readings of {:>2} bits: per bit {:>10.3?}, sliced {:>10.3?} ({:.1}x)",
+            READINGS,
+            bits,
+            simple_time,
+            sliced_time,
+            simple_time.as_secs_f64() / sliced_time.as_secs_f64()
+        );
+    }
+}
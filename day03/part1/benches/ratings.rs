@@ -0,0 +1,60 @@
+use std::time::{Duration, Instant};
+
+use day03_part1::{
+    filter::{filter, BitOrder, LeastCommon, MostCommon},
+    testing::xorshift,
+    trie::Trie,
+};
+
+const BITS: usize = 12;
+const READINGS: usize = 1_000_000;
+
+fn readings() -> Vec<u32> {
+    xorshift(0x2545_f491_4f6c_dd1d)
+        .map(|state| (state % (1 << BITS)) as u32)
+        .take(READINGS)
+        .collect()
+}
+
+fn time<T>(name: &str, f: impl Fn() -> T) -> (T, Duration) {
+    let runs = 5;
+    let start = Instant::now();
+    let mut result = f();
+    for _ in 1..runs {
+        result = f();
+    }
+    let elapsed = start.elapsed() / runs;
+    println!("{:<24} {:>10.3?}", name, elapsed);
+    (result, elapsed)
+}
+
+fn main() {
+    let readings = readings();
+    println!("{} readings of {} bits", READINGS, BITS);
+
+    let (filtered, filter_time) = time("filter", || {
+        (
+            filter(BITS, readings.clone(), &MostCommon::default()).unwrap(),
+            filter(BITS, readings.clone(), &LeastCommon::default()).unwrap(),
+        )
+    });
+
+    let (trie, build_time) = time("trie build", || {
+        Trie::new(BITS, &readings, BitOrder::MostSignificantFirst).unwrap()
+    });
+    let (queried, query_time) = time("trie queries", || {
+        (
+            trie.query(&MostCommon::default()).unwrap(),
+            trie.query(&LeastCommon::default()).unwrap(),
+        )
+    });
+
+    assert_eq!(filtered, queried);
+    // The build costs more than filtering, so the trie only wins once its readings are
+    // queried more than once.
+    println!(
+        "trie build and queries take {:.1}x the filter time, and each further query pair {:.5}x",
+        (build_time + query_time).as_secs_f64() / filter_time.as_secs_f64(),
+        query_time.as_secs_f64() / filter_time.as_secs_f64()
+    );
+}
@@ -0,0 +1,165 @@
+use crate::{
+    filter::BitOrder, rates_from_counts, trie::Trie, Rates, Rating, ReportError, TiePolicy, Word,
+};
+
+/// Keeps a report's bit counts and rating index up to date as readings arrive and are
+/// retracted, so every answer is available without going back over the readings.
+#[derive(Debug, Clone)]
+pub struct DiagnosticAccumulator<W> {
+    bits: usize,
+    policy: TiePolicy,
+    counters: Vec<usize>,
+    index: Trie<W>,
+}
+
+impl<W: Word> DiagnosticAccumulator<W> {
+    /// Fails with `ReportError::TooWide` when `W` cannot hold `bits` bits.
+    pub fn new(bits: usize, policy: TiePolicy) -> Result<Self, ReportError> {
+        Ok(DiagnosticAccumulator {
+            bits,
+            policy,
+            counters: vec![0; bits],
+            index: Trie::new(bits, &[], BitOrder::MostSignificantFirst)?,
+        })
+    }
+
+    /// Adds a reading; bits beyond the report width are ignored.
+    pub fn add(&mut self, value: W) {
+        for (i, counter) in self.counters.iter_mut().enumerate() {
+            if value.bit(i) {
+                *counter += 1;
+            }
+        }
+        self.index.insert(&value);
+    }
+
+    /// Retracts one earlier occurrence of `value`, returning whether there was one.
+    pub fn remove(&mut self, value: &W) -> bool {
+        if !self.index.remove(value) {
+            return false;
+        }
+        for (i, counter) in self.counters.iter_mut().enumerate() {
+            if value.bit(i) {
+                *counter -= 1;
+            }
+        }
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    pub fn bits(&self) -> usize {
+        self.bits
+    }
+
+    /// Fails with `ReportError::Empty` while there are no readings, like every answer below.
+    pub fn rates(&self) -> Result<Rates<W>, ReportError> {
+        if self.is_empty() {
+            return Err(ReportError::Empty);
+        }
+        rates_from_counts(self.len(), &self.counters, self.policy).map_err(ReportError::Tie)
+    }
+
+    pub fn power_consumption(&self) -> Result<W::Wide, ReportError> {
+        let rates = self.rates()?;
+        Ok(rates.gamma.wide_mul(&rates.epsilon))
+    }
+
+    pub fn oxygen_generator(&self) -> Result<Rating<W>, ReportError> {
+        self.index.oxygen_generator(self.policy)
+    }
+
+    pub fn co2_scrubber(&self) -> Result<Rating<W>, ReportError> {
+        self.index.co2_scrubber(self.policy)
+    }
+
+    pub fn life_support_rating(&self) -> Result<W::Wide, ReportError> {
+        let oxygen_generator = self.oxygen_generator()?;
+        let co2_scrubber = self.co2_scrubber()?;
+        Ok(oxygen_generator.value.wide_mul(&co2_scrubber.value))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        calculate_co2_scrubber, calculate_oxygen_generator, calculate_rates, testing::example,
+    };
+
+    fn assert_matches_batch(accumulator: &DiagnosticAccumulator<u32>, readings: &[u32]) {
+        let policy = TiePolicy::PreferOne;
+        assert_eq!(accumulator.len(), readings.len());
+        assert_eq!(
+            accumulator.rates(),
+            calculate_rates(5, &mut readings.iter().copied(), policy)
+        );
+        assert_eq!(
+            accumulator.oxygen_generator(),
+            calculate_oxygen_generator(5, readings.to_vec(), policy)
+        );
+        assert_eq!(
+            accumulator.co2_scrubber(),
+            calculate_co2_scrubber(5, readings.to_vec(), policy)
+        );
+    }
+
+    #[test]
+    fn example_incremental() {
+        let mut accumulator = DiagnosticAccumulator::new(5, TiePolicy::PreferOne).unwrap();
+        assert_eq!(accumulator.rates(), Err(ReportError::Empty));
+        assert_eq!(accumulator.power_consumption(), Err(ReportError::Empty));
+        assert_eq!(accumulator.oxygen_generator(), Err(ReportError::Empty));
+        assert_eq!(accumulator.life_support_rating(), Err(ReportError::Empty));
+
+        let readings = example();
+        for (count, &value) in readings.iter().enumerate() {
+            accumulator.add(value);
+            assert_matches_batch(&accumulator, &readings[..=count]);
+        }
+        assert_eq!(accumulator.power_consumption(), Ok(198));
+        assert_eq!(accumulator.life_support_rating(), Ok(230));
+    }
+
+    #[test]
+    fn remove_readings() {
+        let mut accumulator = DiagnosticAccumulator::new(5, TiePolicy::PreferOne).unwrap();
+        for value in example() {
+            accumulator.add(value);
+        }
+        accumulator.add(23);
+
+        assert!(accumulator.remove(&23));
+        assert_matches_batch(&accumulator, &example());
+
+        assert!(!accumulator.remove(&31));
+        assert!(accumulator.remove(&23));
+        assert!(!accumulator.remove(&23));
+        let remaining: Vec<u32> = example().into_iter().filter(|&value| value != 23).collect();
+        assert_matches_batch(&accumulator, &remaining);
+
+        for value in remaining {
+            assert!(accumulator.remove(&value));
+        }
+        assert!(accumulator.is_empty());
+        assert_eq!(accumulator.rates(), Err(ReportError::Empty));
+        assert_eq!(accumulator.co2_scrubber(), Err(ReportError::Empty));
+    }
+
+    #[test]
+    fn too_wide() {
+        assert_eq!(
+            DiagnosticAccumulator::<u32>::new(40, TiePolicy::PreferOne).err(),
+            Some(ReportError::TooWide {
+                bits: 40,
+                capacity: 32
+            })
+        );
+    }
+}
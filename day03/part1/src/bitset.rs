@@ -0,0 +1,163 @@
+use std::{cmp::Ordering, fmt, ops::Mul};
+
+/// An unbounded unsigned integer stored as little-endian 64-bit limbs, doubling as a set of bits.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct BitSet {
+    /// Never ends in a zero limb, so equal values have equal representations.
+    limbs: Vec<u64>,
+}
+
+impl BitSet {
+    pub fn new() -> Self {
+        BitSet::default()
+    }
+
+    fn trim(mut self) -> Self {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    pub fn bit(&self, index: usize) -> bool {
+        self.limbs
+            .get(index / 64)
+            .is_some_and(|limb| limb >> (index % 64) & 1 == 1)
+    }
+
+    pub fn set_bit(&mut self, index: usize) {
+        if self.limbs.len() <= index / 64 {
+            self.limbs.resize(index / 64 + 1, 0);
+        }
+        self.limbs[index / 64] |= 1 << (index % 64);
+    }
+
+    /// Bits `64 * index` up to `64 * index + 63`, lowest bit first.
+    pub fn limb(&self, index: usize) -> u64 {
+        self.limbs.get(index).copied().unwrap_or(0)
+    }
+
+    /// Number of bits up to and including the highest set bit.
+    pub fn len(&self) -> usize {
+        match self.limbs.last() {
+            Some(last) => self.limbs.len() * 64 - last.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    /// Divides in place by a single limb, returning the remainder.
+    fn div_rem_small(&mut self, divisor: u64) -> u64 {
+        let mut remainder = 0u128;
+        for limb in self.limbs.iter_mut().rev() {
+            let value = remainder << 64 | *limb as u128;
+            *limb = (value / divisor as u128) as u64;
+            remainder = value % divisor as u128;
+        }
+        *self = std::mem::take(self).trim();
+        remainder as u64
+    }
+}
+
+impl From<u128> for BitSet {
+    fn from(value: u128) -> Self {
+        BitSet {
+            limbs: vec![value as u64, (value >> 64) as u64],
+        }
+        .trim()
+    }
+}
+
+impl Mul<&BitSet> for &BitSet {
+    type Output = BitSet;
+
+    fn mul(self, other: &BitSet) -> BitSet {
+        let mut limbs = vec![0u64; self.limbs.len() + other.limbs.len()];
+        for (i, &left) in self.limbs.iter().enumerate() {
+            let mut carry = 0u128;
+            for (j, &right) in other.limbs.iter().enumerate() {
+                let product = left as u128 * right as u128 + limbs[i + j] as u128 + carry;
+                limbs[i + j] = product as u64;
+                carry = product >> 64;
+            }
+            limbs[i + other.limbs.len()] = carry as u64;
+        }
+        BitSet { limbs }.trim()
+    }
+}
+
+impl Ord for BitSet {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BitSet {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BitSet {
+    /// Formats the value in decimal.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const CHUNK: u64 = 10_000_000_000_000_000_000;
+
+        let mut value = self.clone();
+        let mut chunks = vec![];
+        while !value.is_empty() {
+            chunks.push(value.div_rem_small(CHUNK));
+        }
+
+        match chunks.split_last() {
+            None => f.pad("0"),
+            Some((first, rest)) => {
+                let mut digits = first.to_string();
+                for chunk in rest.iter().rev() {
+                    digits.push_str(&format!("{:019}", chunk));
+                }
+                f.pad(&digits)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bits_and_len() {
+        let mut set = BitSet::new();
+        assert_eq!(set.len(), 0);
+        set.set_bit(3);
+        set.set_bit(130);
+        assert!(set.bit(3) && set.bit(130));
+        assert!(!set.bit(4) && !set.bit(500));
+        assert_eq!(set.len(), 131);
+    }
+
+    #[test]
+    fn multiply_and_display() {
+        let left = BitSet::from(u128::MAX);
+        let product = &left * &left;
+
+        assert_eq!(
+            product.to_string(),
+            "115792089237316195423570985008687907852589419931798687112530834793049593217025"
+        );
+        assert_eq!((&BitSet::from(22) * &BitSet::from(9)).to_string(), "198");
+        assert_eq!((&BitSet::new() * &left).to_string(), "0");
+        assert_eq!(
+            BitSet::from(10_000_000_000_000_000_000).to_string(),
+            "10000000000000000000"
+        );
+        assert!(BitSet::from(1 << 64) > BitSet::from(u64::MAX as u128));
+    }
+}
@@ -0,0 +1,96 @@
+use crate::encoding::{Encoding, InputOrder, ParseEncodingError};
+
+/// The command line of the day's binaries: `--encoding=NAME` and `--lsb-first` say how reports
+/// are written, each binary may accept flags of its own, and every other argument is a path.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Options {
+    pub encoding: Encoding,
+    pub order: InputOrder,
+    /// The binary's own flags that were given.
+    pub flags: Vec<String>,
+    pub paths: Vec<String>,
+}
+
+impl Options {
+    /// Parses `arguments`, accepting each of `flags` as a flag of the binary's own.
+    pub fn parse(
+        arguments: impl IntoIterator<Item = String>,
+        flags: &[&str],
+    ) -> Result<Options, ParseEncodingError> {
+        let mut options = Options::default();
+        for argument in arguments {
+            match argument.as_str() {
+                "--lsb-first" => options.order = InputOrder::LeastSignificantFirst,
+                flag if flags.contains(&flag) => options.flags.push(argument),
+                _ => match argument.strip_prefix("--encoding=") {
+                    Some(name) => options.encoding = name.parse()?,
+                    None => options.paths.push(argument),
+                },
+            }
+        }
+        Ok(options)
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|flag| flag == name)
+    }
+}
+
+/// Calls `function::<W>(arguments...)` with `W` the word type `width` picks.
+#[macro_export]
+macro_rules! with_word {
+    ($width:expr, $function:ident($($argument:expr),* $(,)?)) => {
+        match $width {
+            $crate::Width::U32 => $function::<u32>($($argument),*),
+            $crate::Width::U64 => $function::<u64>($($argument),*),
+            $crate::Width::U128 => $function::<u128>($($argument),*),
+            $crate::Width::BitSet => $function::<$crate::BitSet>($($argument),*),
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Width, Word};
+
+    fn arguments(text: &str) -> Vec<String> {
+        text.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parse_options() {
+        let options = Options::parse(
+            arguments("--trace --encoding=hex before.txt --lsb-first after.txt --stats"),
+            &["--trace"],
+        )
+        .unwrap();
+
+        assert_eq!(
+            options,
+            Options {
+                encoding: Encoding::Hex,
+                order: InputOrder::LeastSignificantFirst,
+                flags: arguments("--trace"),
+                paths: arguments("before.txt after.txt --stats"),
+            }
+        );
+        assert!(options.flag("--trace"));
+        assert!(!options.flag("--stats"));
+        assert_eq!(
+            Options::parse(arguments("--encoding=base64"), &[]),
+            Err(ParseEncodingError("base64".to_string()))
+        );
+    }
+
+    #[test]
+    fn word_for_width() {
+        fn capacity<W: Word>(extra: usize) -> Option<usize> {
+            W::CAPACITY.map(|capacity| capacity + extra)
+        }
+
+        assert_eq!(with_word!(Width::detect(5), capacity(1)), Some(33));
+        assert_eq!(with_word!(Width::detect(100), capacity(0)), Some(128));
+        assert_eq!(with_word!(Width::detect(500), capacity(0)), None);
+    }
+}
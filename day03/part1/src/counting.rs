@@ -0,0 +1,148 @@
+use crate::Word;
+
+/// Bit planes per vertical counter, so each column counts up to `2^PLANES - 1` before a flush.
+const PLANES: usize = 16;
+const FLUSH_EVERY: usize = (1 << PLANES) - 1;
+
+/// Counts, for every bit position, how many readings have it set, returning the number of
+/// readings alongside. Tests each bit of each reading in turn.
+pub fn count_ones<W: Word>(
+    bits: usize,
+    diagnostic_input: &mut dyn Iterator<Item = W>,
+) -> (usize, Vec<usize>) {
+    let mut counters = vec![0usize; bits];
+    let mut total = 0;
+    for value in diagnostic_input {
+        total += 1;
+        for (i, counter) in counters.iter_mut().enumerate() {
+            if value.bit(i) {
+                *counter += 1;
+            }
+        }
+    }
+    (total, counters)
+}
+
+/// Same result as `count_ones`, using vertical counters: 64 columns at a time are kept as bit
+/// planes, where plane `k` holds bit `k` of every column's count, and a reading is added to all
+/// of them at once with a ripple of carries. The planes are flushed into the totals before any
+/// column can overflow.
+pub fn count_ones_sliced<W: Word>(
+    bits: usize,
+    diagnostic_input: &mut dyn Iterator<Item = W>,
+) -> (usize, Vec<usize>) {
+    let limbs = bits.div_ceil(64);
+    let mut planes = vec![[0u64; PLANES]; limbs];
+    let mut counters = vec![0usize; bits];
+    let mut total = 0;
+    let mut pending = 0;
+
+    let flush = |planes: &mut Vec<[u64; PLANES]>, counters: &mut Vec<usize>| {
+        for (bit, counter) in counters.iter_mut().enumerate() {
+            let limb = &planes[bit / 64];
+            *counter += (0..PLANES)
+                .map(|k| ((limb[k] >> (bit % 64) & 1) as usize) << k)
+                .sum::<usize>();
+        }
+        planes.iter_mut().for_each(|limb| *limb = [0; PLANES]);
+    };
+
+    for value in diagnostic_input {
+        for (index, limb) in planes.iter_mut().enumerate() {
+            let mut carry = value.limb(index);
+            for plane in limb.iter_mut() {
+                if carry == 0 {
+                    break;
+                }
+                let overflow = *plane & carry;
+                *plane ^= carry;
+                carry = overflow;
+            }
+        }
+
+        total += 1;
+        pending += 1;
+        if pending == FLUSH_EVERY {
+            flush(&mut planes, &mut counters);
+            pending = 0;
+        }
+    }
+    flush(&mut planes, &mut counters);
+
+    (total, counters)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        testing::{example, xorshift},
+        BitSet,
+    };
+
+    fn random_readings(seed: u64, length: usize) -> Vec<u128> {
+        let mut state = xorshift(seed);
+        (0..length)
+            .map(|_| {
+                let mut next = || state.next().unwrap();
+                (next() as u128) << 64 | next() as u128
+            })
+            .collect()
+    }
+
+    #[test]
+    fn example_counts() {
+        let input = example();
+
+        assert_eq!(
+            count_ones_sliced(5, &mut input.iter().copied()),
+            (12, vec![5, 7, 8, 5, 7])
+        );
+    }
+
+    #[test]
+    fn matches_count_ones() {
+        for (seed, length) in [(1, 0), (2, 1), (3, 1000), (4, FLUSH_EVERY + 17)] {
+            let readings = random_readings(seed, length);
+
+            for bits in [1, 12, 64, 100, 128] {
+                assert_eq!(
+                    count_ones_sliced(bits, &mut readings.iter().copied()),
+                    count_ones(bits, &mut readings.iter().copied()),
+                    "{} {}",
+                    seed,
+                    bits
+                );
+            }
+
+            let narrow: Vec<u32> = readings.iter().map(|&value| value as u32).collect();
+            assert_eq!(
+                count_ones_sliced(32, &mut narrow.iter().copied()),
+                count_ones(32, &mut narrow.iter().copied())
+            );
+
+            let wide: Vec<BitSet> = readings
+                .iter()
+                .map(|&value| {
+                    let mut set = BitSet::from(value);
+                    set.set_bit(200);
+                    set
+                })
+                .collect();
+            assert_eq!(
+                count_ones_sliced(201, &mut wide.iter().cloned()),
+                count_ones(201, &mut wide.iter().cloned())
+            );
+        }
+    }
+
+    #[test]
+    fn saturated_columns() {
+        let readings = vec![u64::MAX; FLUSH_EVERY * 2 + 3];
+
+        let (total, counters) = count_ones_sliced(64, &mut readings.into_iter());
+
+        assert_eq!(total, FLUSH_EVERY * 2 + 3);
+        assert!(counters.iter().all(|&count| count == total));
+    }
+}
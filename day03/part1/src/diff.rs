@@ -0,0 +1,306 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    check_width,
+    counting::count_ones_sliced,
+    filter::{filter_traced, Criteria, LeastCommon, MostCommon},
+    list_readings, rates_from_counts, Rates, Rating, ReportError, TiePolicy, Word,
+};
+
+/// How many readings had a bit set in each report.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct BitChange {
+    pub bit: usize,
+    pub before: usize,
+    pub after: usize,
+}
+
+impl BitChange {
+    pub fn change(&self) -> isize {
+        self.after as isize - self.before as isize
+    }
+}
+
+/// A rating from each report, with the bit positions where the filters kept different values.
+///
+/// The rating can change with no differing decision when the readings themselves changed. Bits
+/// that only one of the filters reached are not compared.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RatingChange<W> {
+    pub before: Rating<W>,
+    pub after: Rating<W>,
+    /// In the order the filters considered them.
+    pub decisions: Vec<usize>,
+}
+
+impl<W: Word> RatingChange<W> {
+    pub fn changed(&self) -> bool {
+        self.before.value != self.after.value
+    }
+}
+
+/// What changed between two reports of the same width.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ReportDiff<W> {
+    pub width: usize,
+    pub totals: (usize, usize),
+    /// Indexed by bit position.
+    pub bits: Vec<BitChange>,
+    pub rates: (Rates<W>, Rates<W>),
+    pub oxygen_generator: RatingChange<W>,
+    pub co2_scrubber: RatingChange<W>,
+    /// Readings in the first report more often than in the second, once per extra occurrence, in
+    /// the order they appear.
+    pub only_before: Vec<W>,
+    /// Readings in the second report more often than in the first, likewise.
+    pub only_after: Vec<W>,
+}
+
+impl<W: Word> ReportDiff<W> {
+    /// Bit positions where the most common value flipped, changing both gamma and epsilon,
+    /// lowest first.
+    pub fn rate_bits(&self) -> Vec<usize> {
+        let (before, after) = &self.rates;
+        (0..self.width)
+            .filter(|&bit| {
+                before.gamma.bit(bit) != after.gamma.bit(bit)
+                    || before.epsilon.bit(bit) != after.epsilon.bit(bit)
+            })
+            .collect()
+    }
+}
+
+/// Each reading of `left` that has no counterpart left in `right`, matching occurrences one to
+/// one.
+fn unmatched<W: Word>(left: &[W], right: &[W]) -> Vec<W> {
+    let mut counts: HashMap<&W, usize> = HashMap::new();
+    for value in right {
+        *counts.entry(value).or_insert(0) += 1;
+    }
+    left.iter()
+        .filter(|&value| match counts.get_mut(value) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                false
+            }
+            _ => true,
+        })
+        .cloned()
+        .collect()
+}
+
+fn compare_ratings<W: Word, C: Criteria>(
+    width: usize,
+    before: &[W],
+    after: &[W],
+    criteria: &C,
+) -> Result<RatingChange<W>, ReportError> {
+    let (before, before_trace) = filter_traced(width, before.to_vec(), criteria)?;
+    let (after, after_trace) = filter_traced(width, after.to_vec(), criteria)?;
+    let decisions = before_trace
+        .steps
+        .iter()
+        .zip(&after_trace.steps)
+        .filter(|(before, after)| before.kept != after.kept)
+        .map(|(before, _)| before.bit)
+        .collect();
+    Ok(RatingChange {
+        before,
+        after,
+        decisions,
+    })
+}
+
+/// Compares two reports `width` bits wide as the rates and ratings see them under `policy`.
+///
+/// Fails with `ReportError::Empty` if either report has no readings.
+pub fn compare<W: Word>(
+    width: usize,
+    before: &[W],
+    after: &[W],
+    policy: TiePolicy,
+) -> Result<ReportDiff<W>, ReportError> {
+    check_width::<W>(width)?;
+    if before.is_empty() || after.is_empty() {
+        return Err(ReportError::Empty);
+    }
+
+    let (_, before_ones) = count_ones_sliced(width, &mut before.iter().cloned());
+    let (_, after_ones) = count_ones_sliced(width, &mut after.iter().cloned());
+    let bits = (0..width)
+        .map(|bit| BitChange {
+            bit,
+            before: before_ones[bit],
+            after: after_ones[bit],
+        })
+        .collect();
+
+    let rates = (
+        rates_from_counts(before.len(), &before_ones, policy).map_err(ReportError::Tie)?,
+        rates_from_counts(after.len(), &after_ones, policy).map_err(ReportError::Tie)?,
+    );
+    let most_common = MostCommon {
+        policy,
+        ..MostCommon::default()
+    };
+    let least_common = LeastCommon {
+        policy,
+        ..LeastCommon::default()
+    };
+
+    Ok(ReportDiff {
+        width,
+        totals: (before.len(), after.len()),
+        bits,
+        rates,
+        oxygen_generator: compare_ratings(width, before, after, &most_common)?,
+        co2_scrubber: compare_ratings(width, before, after, &least_common)?,
+        only_before: unmatched(before, after),
+        only_after: unmatched(after, before),
+    })
+}
+
+impl<W: Word> fmt::Display for ReportDiff<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} readings of {} bits before, {} after",
+            self.totals.0, self.width, self.totals.1
+        )?;
+        writeln!(
+            f,
+            "{:>4} {:>8} {:>8} {:>8}",
+            "bit", "before", "after", "change"
+        )?;
+        for change in self.bits.iter().rev() {
+            writeln!(
+                f,
+                "{:>4} {:>8} {:>8} {:>+8}",
+                change.bit,
+                change.before,
+                change.after,
+                change.change()
+            )?;
+        }
+
+        let (before, after) = &self.rates;
+        let rate_bits = self.rate_bits();
+        if rate_bits.is_empty() {
+            writeln!(f, "Gamma and epsilon unchanged")?;
+        } else {
+            writeln!(
+                f,
+                "Gamma {} -> {}, epsilon {} -> {}, flipped at bits {:?}",
+                before.gamma, after.gamma, before.epsilon, after.epsilon, rate_bits
+            )?;
+        }
+
+        for (name, change) in [
+            ("Oxygen generator", &self.oxygen_generator),
+            ("CO2 scrubber", &self.co2_scrubber),
+        ] {
+            if !change.changed() {
+                writeln!(f, "{} unchanged", name)?;
+            } else if change.decisions.is_empty() {
+                writeln!(
+                    f,
+                    "{} {} -> {}, with the same decisions",
+                    name, change.before.value, change.after.value
+                )?;
+            } else {
+                writeln!(
+                    f,
+                    "{} {} -> {}, decided differently at bits {:?}",
+                    name, change.before.value, change.after.value, change.decisions
+                )?;
+            }
+        }
+
+        for (name, values) in [("before", &self.only_before), ("after", &self.only_after)] {
+            let listed = list_readings(values, self.width);
+            write!(f, "Only {} ({})", name, values.len())?;
+            if !listed.is_empty() {
+                write!(f, ": {}", listed)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::example;
+
+    #[test]
+    fn example_unchanged() {
+        let diff = compare(5, &example(), &example(), TiePolicy::PreferOne).unwrap();
+
+        assert!(diff.bits.iter().all(|change| change.change() == 0));
+        assert_eq!(diff.rate_bits(), vec![]);
+        assert!(!diff.oxygen_generator.changed());
+        assert!(!diff.co2_scrubber.changed());
+        assert_eq!(diff.only_before, vec![]);
+        assert!(diff.to_string().ends_with(
+            "Gamma and epsilon unchanged\n\
+             Oxygen generator unchanged\n\
+             CO2 scrubber unchanged\n\
+             Only before (0)\n\
+             Only after (0)\n"
+        ));
+    }
+
+    #[test]
+    fn example_maintenance() {
+        let mut after = example();
+        after.retain(|&value| value != 23 && value != 21);
+        after.extend([0b01101, 0b01100, 4]);
+
+        let diff = compare(5, &example(), &after, TiePolicy::PreferOne).unwrap();
+
+        assert_eq!(diff.totals, (12, 13));
+        assert_eq!(
+            diff.bits[4],
+            BitChange {
+                bit: 4,
+                before: 7,
+                after: 5
+            }
+        );
+        assert_eq!(diff.bits[3].change(), 2);
+        assert_eq!(diff.rate_bits(), vec![1, 3, 4]);
+        assert_eq!(diff.oxygen_generator.before.value, 23);
+        assert_eq!(diff.oxygen_generator.after.value, 13);
+        assert_eq!(diff.oxygen_generator.decisions, vec![4, 3, 1]);
+        assert_eq!(diff.co2_scrubber.after.value, 16);
+        assert_eq!(diff.co2_scrubber.decisions, vec![4, 3]);
+        assert_eq!(diff.only_before, vec![23, 21]);
+        assert_eq!(diff.only_after, vec![0b01101, 0b01100, 4]);
+
+        let rendered = diff.to_string();
+        assert!(rendered.contains("   4        7        5       -2\n"));
+        assert!(rendered.contains("flipped at bits [1, 3, 4]\n"));
+        assert!(
+            rendered.contains("Oxygen generator 23 -> 13, decided differently at bits [4, 3, 1]\n")
+        );
+        assert!(
+            rendered.ends_with("Only before (2): 10111 10101\nOnly after (3): 01101 01100 00100\n")
+        );
+    }
+
+    #[test]
+    fn empty_reports() {
+        let policy = TiePolicy::PreferOne;
+
+        assert_eq!(compare(5, &example(), &[], policy), Err(ReportError::Empty));
+        assert_eq!(compare::<u32>(5, &[], &[], policy), Err(ReportError::Empty));
+        assert_eq!(
+            compare(40, &example(), &example(), policy),
+            Err(ReportError::TooWide {
+                bits: 40,
+                capacity: 32
+            })
+        );
+    }
+}
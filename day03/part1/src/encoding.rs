@@ -0,0 +1,430 @@
+use std::{fmt, str::FromStr};
+
+use crate::{line_width, lines, ParseError, Word};
+
+/// Which end of a reading the input writes first.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum InputOrder {
+    /// The first digit or bit holds the highest bits, as numbers are usually written.
+    #[default]
+    MostSignificantFirst,
+    /// The first digit or bit holds bit `0`.
+    LeastSignificantFirst,
+}
+
+/// How the readings of a report are written down.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Encoding {
+    /// One reading per line in base 2, as the puzzle gives them.
+    #[default]
+    Binary,
+    /// One reading per line in base 8, three bits to a digit.
+    Octal,
+    /// One reading per line in base 16 in either case, four bits to a digit.
+    Hex,
+    /// Readings of `bits` bits each, back to back in a byte stream with nothing between them.
+    ///
+    /// The stream may end with up to seven bits of padding. When readings are narrower than a
+    /// byte, padding cannot be told apart from a trailing zero reading, so every whole reading
+    /// in the stream is taken.
+    Packed { bits: usize },
+}
+
+impl Encoding {
+    fn radix(self) -> Option<u32> {
+        match self {
+            Encoding::Binary => Some(2),
+            Encoding::Octal => Some(8),
+            Encoding::Hex => Some(16),
+            Encoding::Packed { .. } => None,
+        }
+    }
+
+    /// The number of bits in each reading: the digits of the first line times the bits per
+    /// digit for text, and the given width for packed readings.
+    pub fn width(self, input: &[u8]) -> Result<usize, ParseError> {
+        match (self, self.radix()) {
+            (_, Some(radix)) => {
+                Ok(line_width(&String::from_utf8_lossy(input))? * digit_bits(radix))
+            }
+            (Encoding::Packed { bits: 0 }, None) => Err(ParseError::NoBits),
+            (Encoding::Packed { bits }, None) if !input.is_empty() => Ok(bits),
+            (_, None) => Err(ParseError::Empty),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseEncodingError(pub String);
+
+impl fmt::Display for ParseEncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` is not an encoding, expected `binary`, `octal`, `hex` or `packed:BITS`",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseEncodingError {}
+
+impl FromStr for Encoding {
+    type Err = ParseEncodingError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text {
+            "binary" | "bin" => Ok(Encoding::Binary),
+            "octal" | "oct" => Ok(Encoding::Octal),
+            "hex" => Ok(Encoding::Hex),
+            _ => match text.strip_prefix("packed:").map(str::parse) {
+                Some(Ok(bits)) if bits > 0 => Ok(Encoding::Packed { bits }),
+                _ => Err(ParseEncodingError(text.to_string())),
+            },
+        }
+    }
+}
+
+fn digit_bits(radix: u32) -> usize {
+    radix.trailing_zeros() as usize
+}
+
+/// Decodes a report written in `encoding`, returning its width and readings.
+///
+/// `order` says which end of a reading comes first. Text is checked line by line as `parse_input` does,
+/// with widths counted in bits rather than digits; invalid UTF-8 is reported as an invalid
+/// character.
+pub fn decode<W: Word>(
+    input: &[u8],
+    encoding: Encoding,
+    order: InputOrder,
+) -> Result<(usize, Vec<W>), ParseError> {
+    match (encoding, encoding.radix()) {
+        (_, Some(radix)) => decode_text(&String::from_utf8_lossy(input), radix, order),
+        (Encoding::Packed { bits }, None) => decode_packed(input, bits, order),
+        (_, None) => unreachable!("only packed readings have no radix"),
+    }
+}
+
+pub(crate) fn decode_text<W: Word>(
+    input: &str,
+    radix: u32,
+    order: InputOrder,
+) -> Result<(usize, Vec<W>), ParseError> {
+    let digit_bits = digit_bits(radix);
+    parse_lines::<W, _, _>(
+        input,
+        digit_bits,
+        order,
+        |character| character.to_digit(radix),
+        |digits| {
+            let mut word = W::zero();
+            for &(lowest, digit) in digits {
+                for offset in 0..digit_bits {
+                    if digit >> offset & 1 == 1 {
+                        word.set_bit(lowest + offset);
+                    }
+                }
+            }
+            word
+        },
+    )
+}
+
+/// Parses one reading per line, with `digit` giving the value of each character and each digit
+/// standing for `digit_bits` bits, and checks that every line has the width of the first and
+/// that the report fits in a `W`. `build` turns the digits of a line, each paired with the
+/// lowest bit it stands for under `order`, into a reading.
+pub(crate) fn parse_lines<W: Word, D, T>(
+    input: &str,
+    digit_bits: usize,
+    order: InputOrder,
+    digit: impl Fn(char) -> Option<D>,
+    build: impl Fn(&[(usize, D)]) -> T,
+) -> Result<(usize, Vec<T>), ParseError> {
+    let bits = line_width(input)? * digit_bits;
+
+    let values = lines(input)
+        .map(|(line, text)| {
+            let digits = text
+                .chars()
+                .enumerate()
+                .map(|(index, character)| {
+                    digit(character).ok_or(ParseError::InvalidCharacter {
+                        line,
+                        column: index + 1,
+                        character,
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if digits.len() * digit_bits != bits {
+                return Err(ParseError::WidthMismatch {
+                    line,
+                    expected: bits,
+                    found: digits.len() * digit_bits,
+                });
+            }
+            if let Some(capacity) = W::CAPACITY.filter(|&capacity| bits > capacity) {
+                return Err(ParseError::TooWide {
+                    line,
+                    width: bits,
+                    capacity,
+                });
+            }
+
+            let count = digits.len();
+            let digits: Vec<_> = digits
+                .into_iter()
+                .enumerate()
+                .map(|(position, digit)| {
+                    let lowest = match order {
+                        InputOrder::MostSignificantFirst => (count - 1 - position) * digit_bits,
+                        InputOrder::LeastSignificantFirst => position * digit_bits,
+                    };
+                    (lowest, digit)
+                })
+                .collect();
+            Ok(build(&digits))
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok((bits, values))
+}
+
+/// Within each byte the stream runs from bit `7` down for `MostSignificantFirst` and from bit
+/// `0` up for `LeastSignificantFirst`. A report too wide for `W` is reported against line `1`,
+/// its first reading.
+fn decode_packed<W: Word>(
+    input: &[u8],
+    bits: usize,
+    order: InputOrder,
+) -> Result<(usize, Vec<W>), ParseError> {
+    if bits == 0 {
+        return Err(ParseError::NoBits);
+    }
+    if input.is_empty() {
+        return Err(ParseError::Empty);
+    }
+    if let Some(capacity) = W::CAPACITY.filter(|&capacity| bits > capacity) {
+        return Err(ParseError::TooWide {
+            line: 1,
+            width: bits,
+            capacity,
+        });
+    }
+
+    let stream = input.len() * 8;
+    let (count, leftover) = (stream / bits, stream % bits);
+    if leftover >= 8 {
+        return Err(ParseError::Truncated {
+            reading: count + 1,
+            bits: leftover,
+        });
+    }
+
+    let stream_bit = |position: usize| {
+        let byte = input[position / 8];
+        match order {
+            InputOrder::MostSignificantFirst => byte >> (7 - position % 8) & 1 == 1,
+            InputOrder::LeastSignificantFirst => byte >> (position % 8) & 1 == 1,
+        }
+    };
+    let values = (0..count)
+        .map(|reading| {
+            let mut word = W::zero();
+            for offset in 0..bits {
+                if stream_bit(reading * bits + offset) {
+                    word.set_bit(match order {
+                        InputOrder::MostSignificantFirst => bits - 1 - offset,
+                        InputOrder::LeastSignificantFirst => offset,
+                    });
+                }
+            }
+            word
+        })
+        .collect();
+
+    Ok((bits, values))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        calculate_co2_scrubber, calculate_oxygen_generator, calculate_rates, testing::example,
+        TiePolicy,
+    };
+
+    /// The example's readings, twelve bits at a time, most significant bit first.
+    const PACKED: [u8; 18] = [
+        0x00, 0x40, 0x1E, 0x01, 0x60, 0x17, 0x01, 0x50, 0x0F, 0x00, 0x70, 0x1C, 0x01, 0x00, 0x19,
+        0x00, 0x20, 0x0A,
+    ];
+
+    #[test]
+    fn example_encodings() {
+        let hex = "04\n1E\n16\n17\n15\n0f\n07\n1C\n10\n19\n02\n0A\n";
+        assert_eq!(
+            decode::<u32>(
+                hex.as_bytes(),
+                Encoding::Hex,
+                InputOrder::MostSignificantFirst
+            ),
+            Ok((8, example()))
+        );
+
+        let octal = "04\n36\n26\n27\n25\n17\n07\n34\n20\n31\n02\n12\n";
+        assert_eq!(Encoding::Octal.width(octal.as_bytes()), Ok(6));
+        let (bits, readings) = decode::<u64>(
+            octal.as_bytes(),
+            Encoding::Octal,
+            InputOrder::MostSignificantFirst,
+        )
+        .unwrap();
+        let rates = calculate_rates(bits, &mut readings.iter().copied(), TiePolicy::PreferOne);
+        assert_eq!(rates.unwrap().epsilon, 0b100000 | 9);
+        assert_eq!(
+            calculate_oxygen_generator(bits, readings.clone(), TiePolicy::PreferOne)
+                .unwrap()
+                .value,
+            23
+        );
+        assert_eq!(
+            calculate_co2_scrubber(bits, readings, TiePolicy::PreferOne)
+                .unwrap()
+                .value,
+            10
+        );
+    }
+
+    #[test]
+    fn least_significant_first() {
+        let binary = "00100\n01111\n";
+        assert_eq!(
+            decode::<u32>(
+                binary.as_bytes(),
+                Encoding::Binary,
+                InputOrder::LeastSignificantFirst
+            ),
+            Ok((5, vec![0b00100, 0b11110]))
+        );
+
+        let hex = "1E\nA0\n";
+        assert_eq!(
+            decode::<u32>(
+                hex.as_bytes(),
+                Encoding::Hex,
+                InputOrder::LeastSignificantFirst
+            ),
+            Ok((8, vec![0xE1, 0x0A]))
+        );
+    }
+
+    #[test]
+    fn packed_readings() {
+        let encoding = Encoding::Packed { bits: 12 };
+        let example = example();
+        let (bits, readings) =
+            decode::<u32>(&PACKED, encoding, InputOrder::MostSignificantFirst).unwrap();
+        assert_eq!(bits, 12);
+        assert_eq!(readings, example);
+        let (_, readings) =
+            decode::<u32>(&PACKED[..17], encoding, InputOrder::MostSignificantFirst).unwrap();
+        assert_eq!(readings, example[..11]);
+
+        let five = Encoding::Packed { bits: 5 };
+        let mut bytes = vec![0u8; 8];
+        for (index, &value) in example.iter().enumerate() {
+            for offset in 0..5 {
+                let position = index * 5 + offset;
+                if value >> offset & 1 == 1 {
+                    bytes[position / 8] |= 1 << (position % 8);
+                }
+            }
+        }
+        let (bits, readings) =
+            decode::<u32>(&bytes, five, InputOrder::LeastSignificantFirst).unwrap();
+        assert_eq!(bits, 5);
+        assert_eq!(readings[..12], example[..]);
+        assert_eq!(readings.len(), 12);
+
+        assert_eq!(
+            decode::<u32>(&PACKED[..16], encoding, InputOrder::MostSignificantFirst),
+            Err(ParseError::Truncated {
+                reading: 11,
+                bits: 8
+            })
+        );
+        assert_eq!(
+            decode::<u32>(&[], encoding, InputOrder::MostSignificantFirst),
+            Err(ParseError::Empty)
+        );
+        assert_eq!(encoding.width(&PACKED), Ok(12));
+        let empty = Encoding::Packed { bits: 0 };
+        assert_eq!(empty.width(&PACKED), Err(ParseError::NoBits));
+        assert_eq!(
+            decode::<u32>(&PACKED, empty, InputOrder::MostSignificantFirst),
+            Err(ParseError::NoBits)
+        );
+        assert_eq!(
+            decode::<u32>(
+                &PACKED,
+                Encoding::Packed { bits: 40 },
+                InputOrder::MostSignificantFirst
+            ),
+            Err(ParseError::TooWide {
+                line: 1,
+                width: 40,
+                capacity: 32
+            })
+        );
+    }
+
+    #[test]
+    fn text_errors() {
+        assert_eq!(
+            decode::<u32>(b"1F\n1G\n", Encoding::Hex, InputOrder::MostSignificantFirst),
+            Err(ParseError::InvalidCharacter {
+                line: 2,
+                column: 2,
+                character: 'G'
+            })
+        );
+        assert_eq!(
+            decode::<u32>(
+                b"17\n170\n",
+                Encoding::Octal,
+                InputOrder::MostSignificantFirst
+            ),
+            Err(ParseError::WidthMismatch {
+                line: 2,
+                expected: 6,
+                found: 9
+            })
+        );
+        assert_eq!(
+            decode::<u32>(
+                b"0\xff\n",
+                Encoding::Binary,
+                InputOrder::MostSignificantFirst
+            ),
+            Err(ParseError::InvalidCharacter {
+                line: 1,
+                column: 2,
+                character: char::REPLACEMENT_CHARACTER
+            })
+        );
+    }
+
+    #[test]
+    fn parse_encodings() {
+        assert_eq!("hex".parse(), Ok(Encoding::Hex));
+        assert_eq!("oct".parse(), Ok(Encoding::Octal));
+        assert_eq!("packed:12".parse(), Ok(Encoding::Packed { bits: 12 }));
+        assert_eq!(
+            "packed:0".parse::<Encoding>(),
+            Err(ParseEncodingError("packed:0".to_string()))
+        );
+        assert!("base64".parse::<Encoding>().is_err());
+    }
+}
@@ -0,0 +1,272 @@
+use crate::{
+    check_width,
+    trace::{Trace, TraceStep},
+    Rating, ReportError, TieError, TiePolicy, Word,
+};
+
+/// The order in which bit positions are considered while filtering.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum BitOrder {
+    #[default]
+    MostSignificantFirst,
+    LeastSignificantFirst,
+}
+
+impl BitOrder {
+    pub fn positions(self, bits: usize) -> Box<dyn Iterator<Item = usize>> {
+        match self {
+            BitOrder::MostSignificantFirst => Box::new((0..bits).rev()),
+            BitOrder::LeastSignificantFirst => Box::new(0..bits),
+        }
+    }
+}
+
+/// Decides which readings survive each round of a rating filter.
+pub trait Criteria {
+    /// The value `bit` must have to be kept, given that `ones` of the `total` remaining readings
+    /// have it set, or `None` to keep every reading.
+    fn keep(&self, bit: usize, ones: usize, total: usize) -> Result<Option<bool>, TieError>;
+
+    fn order(&self) -> BitOrder {
+        BitOrder::default()
+    }
+}
+
+/// Keeps readings with the most common value, as the oxygen generator rating does.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct MostCommon {
+    pub policy: TiePolicy,
+    pub order: BitOrder,
+}
+
+impl Criteria for MostCommon {
+    fn keep(&self, bit: usize, ones: usize, total: usize) -> Result<Option<bool>, TieError> {
+        self.policy.most_common(bit, ones, total)
+    }
+
+    fn order(&self) -> BitOrder {
+        self.order
+    }
+}
+
+/// Keeps readings with the least common value, as the CO2 scrubber rating does.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct LeastCommon {
+    pub policy: TiePolicy,
+    pub order: BitOrder,
+}
+
+impl Criteria for LeastCommon {
+    fn keep(&self, bit: usize, ones: usize, total: usize) -> Result<Option<bool>, TieError> {
+        Ok(self
+            .policy
+            .most_common(bit, ones, total)?
+            .map(|most_common| !most_common))
+    }
+
+    fn order(&self) -> BitOrder {
+        self.order
+    }
+}
+
+/// Criteria given by a function of `(bit, ones, total)`, returning the value to keep.
+pub struct Predicate<F> {
+    pub keep: F,
+    pub order: BitOrder,
+}
+
+impl<F: Fn(usize, usize, usize) -> Option<bool>> Criteria for Predicate<F> {
+    fn keep(&self, bit: usize, ones: usize, total: usize) -> Result<Option<bool>, TieError> {
+        Ok((self.keep)(bit, ones, total))
+    }
+
+    fn order(&self) -> BitOrder {
+        self.order
+    }
+}
+
+/// Repeatedly narrows `diagnostic_input` down by `criteria`, one bit position at a time, until a
+/// single reading is left or every position has been considered. A position where no remaining
+/// reading has the wanted value filters nothing out.
+///
+/// Fails with `ReportError::Empty` when there are no readings to begin with.
+pub fn filter<W: Word, C: Criteria + ?Sized>(
+    bits: usize,
+    diagnostic_input: Vec<W>,
+    criteria: &C,
+) -> Result<Rating<W>, ReportError> {
+    check_width::<W>(bits)?;
+    run(bits, diagnostic_input, criteria, known_bit, false, None)
+}
+
+/// Like `filter`, also recording what happened at each bit position.
+pub fn filter_traced<W: Word, C: Criteria + ?Sized>(
+    bits: usize,
+    diagnostic_input: Vec<W>,
+    criteria: &C,
+) -> Result<(Rating<W>, Trace<W>), ReportError> {
+    check_width::<W>(bits)?;
+    let mut trace = Trace {
+        bits,
+        steps: vec![],
+    };
+    let rating = run(
+        bits,
+        diagnostic_input,
+        criteria,
+        known_bit,
+        false,
+        Some(&mut trace),
+    )?;
+    Ok((rating, trace))
+}
+
+fn known_bit<W: Word>(value: &W, index: usize) -> Option<bool> {
+    Some(value.bit(index))
+}
+
+/// The filter behind `filter` and `masked::filter`, reading bit `i` of a reading as
+/// `bit(reading, i)`. Readings where that is `None` are left out of the counts, and survive a
+/// round that filters on the bit only when `keep_unknown` is set.
+pub(crate) fn run<R, C: Criteria + ?Sized>(
+    bits: usize,
+    diagnostic_input: Vec<R>,
+    criteria: &C,
+    bit: impl Fn(&R, usize) -> Option<bool>,
+    keep_unknown: bool,
+    mut trace: Option<&mut Trace<R>>,
+) -> Result<Rating<R>, ReportError> {
+    if diagnostic_input.is_empty() {
+        return Err(ReportError::Empty);
+    }
+    let mut data = diagnostic_input;
+    let mut ties = vec![];
+
+    for i in criteria.order().positions(bits) {
+        if data.len() == 1 {
+            break;
+        }
+
+        let known = data.iter().filter(|value| bit(value, i).is_some()).count();
+        let ones = data
+            .iter()
+            .filter(|value| bit(value, i) == Some(true))
+            .count();
+        if ones * 2 == known {
+            ties.push(i);
+        }
+        let kept = criteria
+            .keep(i, ones, known)
+            .map_err(ReportError::Tie)?
+            .filter(|&wanted| data.iter().any(|value| bit(value, i) == Some(wanted)));
+
+        let mut step = TraceStep {
+            bit: i,
+            ones,
+            zeros: known - ones,
+            kept,
+            eliminated: vec![],
+        };
+        if let Some(wanted) = kept {
+            let survives = |value: &R| bit(value, i).map_or(keep_unknown, |bit| bit == wanted);
+            if trace.is_some() {
+                let (survivors, eliminated) = data.into_iter().partition(|value| survives(value));
+                data = survivors;
+                step.eliminated = eliminated;
+            } else {
+                data.retain(survives);
+            }
+        }
+        if let Some(trace) = trace.as_mut() {
+            trace.steps.push(step);
+        }
+    }
+
+    Ok(Rating {
+        value: data.swap_remove(0),
+        ties,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::example;
+
+    #[test]
+    fn example_configurations() {
+        let oxygen_generator = filter(5, example(), &MostCommon::default()).unwrap();
+        let co2_scrubber = filter(5, example(), &LeastCommon::default()).unwrap();
+
+        assert_eq!(oxygen_generator.value, 23);
+        assert_eq!(co2_scrubber.value, 10);
+    }
+
+    #[test]
+    fn least_significant_first() {
+        let criteria = MostCommon {
+            policy: TiePolicy::PreferZero,
+            order: BitOrder::LeastSignificantFirst,
+        };
+
+        let rating = filter(5, example(), &criteria).unwrap();
+
+        assert_eq!(rating.value, 0b00010);
+        assert_eq!(rating.ties, vec![2, 3]);
+        assert_eq!(
+            BitOrder::LeastSignificantFirst
+                .positions(3)
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+    }
+
+    #[test]
+    fn custom_predicate() {
+        let always_one = Predicate {
+            keep: |_, ones, _| if ones > 0 { Some(true) } else { None },
+            order: BitOrder::MostSignificantFirst,
+        };
+        let rating = filter(5, example(), &always_one).unwrap();
+        assert_eq!(rating.value, 30);
+
+        let two_thirds = Predicate {
+            keep: |_, ones, total| Some(ones * 3 > total * 2),
+            order: BitOrder::MostSignificantFirst,
+        };
+        let criteria: &dyn Criteria = &two_thirds;
+        assert_eq!(filter(5, example(), criteria).unwrap().value, 0b00010);
+    }
+
+    #[test]
+    fn missing_value_filters_nothing() {
+        let readings: Vec<u32> = vec![0b11, 0b11, 0b10];
+
+        let co2_scrubber = filter(2, readings, &LeastCommon::default()).unwrap();
+
+        assert_eq!(co2_scrubber.value, 0b10);
+    }
+
+    #[test]
+    fn example_trace() {
+        let (rating, trace) = filter_traced(5, example(), &LeastCommon::default()).unwrap();
+
+        assert_eq!(
+            rating,
+            filter(5, example(), &LeastCommon::default()).unwrap()
+        );
+        assert_eq!(
+            trace
+                .steps
+                .iter()
+                .map(|step| (step.bit, step.ones, step.zeros, step.kept))
+                .collect::<Vec<_>>(),
+            vec![
+                (4, 7, 5, Some(false)),
+                (3, 2, 3, Some(true)),
+                (2, 1, 1, Some(false)),
+            ]
+        );
+        assert_eq!(trace.steps[1].eliminated, vec![4, 7, 2]);
+    }
+}
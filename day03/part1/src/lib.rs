@@ -0,0 +1,621 @@
+use std::{fmt, hash::Hash};
+
+pub mod accumulator;
+pub mod bitset;
+pub mod cli;
+pub mod counting;
+pub mod diff;
+pub mod encoding;
+pub mod filter;
+pub mod masked;
+pub mod stats;
+#[cfg(any(test, feature = "bench"))]
+#[doc(hidden)]
+pub mod testing;
+pub mod tie;
+pub mod trace;
+pub mod trie;
+
+pub use bitset::BitSet;
+use counting::count_ones_sliced;
+use encoding::InputOrder;
+use filter::{filter, BitOrder, LeastCommon, MostCommon};
+pub use tie::{TieError, TiePolicy};
+
+/// A diagnostic reading, with bit `0` being the rightmost digit of its line.
+pub trait Word: Clone + fmt::Debug + fmt::Display + Eq + Hash {
+    /// Result of multiplying two words, wide enough that it never overflows.
+    type Wide: fmt::Display + fmt::Debug + Eq;
+
+    /// Most bits a word can hold, or `None` when unbounded.
+    const CAPACITY: Option<usize>;
+
+    fn zero() -> Self;
+    fn bit(&self, index: usize) -> bool;
+    fn set_bit(&mut self, index: usize);
+    fn wide_mul(&self, other: &Self) -> Self::Wide;
+
+    /// Bits `64 * index` up to `64 * index + 63`, lowest bit first.
+    fn limb(&self, index: usize) -> u64 {
+        (0..64)
+            .filter(|offset| self.bit(64 * index + offset))
+            .fold(0, |limb, offset| limb | 1 << offset)
+    }
+}
+
+macro_rules! impl_word {
+    ($word:ty, $wide:ty) => {
+        impl Word for $word {
+            type Wide = $wide;
+
+            const CAPACITY: Option<usize> = Some(<$word>::BITS as usize);
+
+            fn zero() -> Self {
+                0
+            }
+
+            fn bit(&self, index: usize) -> bool {
+                self >> index & 1 == 1
+            }
+
+            fn set_bit(&mut self, index: usize) {
+                *self |= 1 << index;
+            }
+
+            fn wide_mul(&self, other: &Self) -> $wide {
+                *self as $wide * *other as $wide
+            }
+
+            fn limb(&self, index: usize) -> u64 {
+                if index == 0 {
+                    *self as u64
+                } else {
+                    0
+                }
+            }
+        }
+    };
+}
+
+impl_word!(u32, u64);
+impl_word!(u64, u128);
+
+impl Word for u128 {
+    type Wide = BitSet;
+
+    const CAPACITY: Option<usize> = Some(128);
+
+    fn zero() -> Self {
+        0
+    }
+
+    fn bit(&self, index: usize) -> bool {
+        self >> index & 1 == 1
+    }
+
+    fn set_bit(&mut self, index: usize) {
+        *self |= 1 << index;
+    }
+
+    fn wide_mul(&self, other: &Self) -> BitSet {
+        &BitSet::from(*self) * &BitSet::from(*other)
+    }
+
+    fn limb(&self, index: usize) -> u64 {
+        match index {
+            0 => *self as u64,
+            1 => (*self >> 64) as u64,
+            _ => 0,
+        }
+    }
+}
+
+impl Word for BitSet {
+    type Wide = BitSet;
+
+    const CAPACITY: Option<usize> = None;
+
+    fn zero() -> Self {
+        BitSet::new()
+    }
+
+    fn bit(&self, index: usize) -> bool {
+        BitSet::bit(self, index)
+    }
+
+    fn set_bit(&mut self, index: usize) {
+        BitSet::set_bit(self, index)
+    }
+
+    fn wide_mul(&self, other: &Self) -> BitSet {
+        self * other
+    }
+
+    fn limb(&self, index: usize) -> u64 {
+        BitSet::limb(self, index)
+    }
+}
+
+/// Formats the low `bits` bits of `value` as a line of the report would show them.
+pub fn format_binary<W: Word>(value: &W, bits: usize) -> String {
+    (0..bits)
+        .rev()
+        .map(|bit| if value.bit(bit) { '1' } else { '0' })
+        .collect()
+}
+
+/// Readings listed by `list_readings` before the rest are summarised as a count.
+const LISTED: usize = 8;
+
+/// Formats the first few of `values` as `format_binary` does, separated by spaces, with a count
+/// of any that are left out.
+pub(crate) fn list_readings<W: Word>(values: &[W], bits: usize) -> String {
+    let mut listed: Vec<String> = values
+        .iter()
+        .take(LISTED)
+        .map(|value| format_binary(value, bits))
+        .collect();
+    if values.len() > LISTED {
+        listed.push(format!("... ({} more)", values.len() - LISTED));
+    }
+    listed.join(" ")
+}
+
+/// The narrowest word type that holds a report of a given width.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Width {
+    U32,
+    U64,
+    U128,
+    BitSet,
+}
+
+impl Width {
+    pub fn detect(bits: usize) -> Width {
+        match bits {
+            0..=32 => Width::U32,
+            33..=64 => Width::U64,
+            65..=128 => Width::U128,
+            _ => Width::BitSet,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ParseError {
+    Empty,
+    WidthMismatch {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    InvalidCharacter {
+        line: usize,
+        column: usize,
+        character: char,
+    },
+    /// The report is wider than the word type chosen to hold it.
+    TooWide {
+        line: usize,
+        width: usize,
+        capacity: usize,
+    },
+    /// Packed input that stops partway through a reading, `bits` bits into it.
+    Truncated {
+        reading: usize,
+        bits: usize,
+    },
+    /// Packed readings that were given no bits.
+    NoBits,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "the report has no readings"),
+            ParseError::WidthMismatch {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {}: expected {} bits but found {}",
+                line, expected, found
+            ),
+            ParseError::InvalidCharacter {
+                line,
+                column,
+                character,
+            } => write!(
+                f,
+                "line {}: `{}` at column {} is not a valid digit",
+                line, character, column
+            ),
+            ParseError::TooWide {
+                line,
+                width,
+                capacity,
+            } => write!(
+                f,
+                "line {}: {} bits do not fit in a {}-bit word",
+                line, width, capacity
+            ),
+            ParseError::Truncated { reading, bits } => {
+                write!(f, "reading {} is cut off after {} bits", reading, bits)
+            }
+            ParseError::NoBits => write!(f, "readings need at least one bit"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Why the rates or a rating of a report could not be worked out.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ReportError {
+    /// There are no readings to rate.
+    Empty,
+    /// The report is wider than the word type chosen to hold it.
+    TooWide {
+        bits: usize,
+        capacity: usize,
+    },
+    Tie(TieError),
+    /// A trie was queried with criteria that consider bits in a different order than it was
+    /// built with.
+    OrderMismatch {
+        expected: BitOrder,
+        found: BitOrder,
+    },
+}
+
+impl fmt::Display for ReportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportError::Empty => write!(f, "the report has no readings"),
+            ReportError::TooWide { bits, capacity } => {
+                write!(f, "{} bits do not fit in a {}-bit word", bits, capacity)
+            }
+            ReportError::Tie(error) => write!(f, "{}", error),
+            ReportError::OrderMismatch { expected, found } => write!(
+                f,
+                "the trie is ordered {:?} but was queried {:?}",
+                expected, found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReportError {}
+
+/// Checks that `bits` bits fit in a `W`.
+pub(crate) fn check_width<W: Word>(bits: usize) -> Result<(), ReportError> {
+    match W::CAPACITY {
+        Some(capacity) if bits > capacity => Err(ReportError::TooWide { bits, capacity }),
+        _ => Ok(()),
+    }
+}
+
+/// Non-blank lines, trimmed and paired with their 1-based line number.
+fn lines(input: &str) -> impl Iterator<Item = (usize, &str)> {
+    input.lines().enumerate().filter_map(|(index, line)| {
+        let line = line.trim();
+        if !line.is_empty() {
+            Some((index + 1, line))
+        } else {
+            None
+        }
+    })
+}
+
+/// The number of bits in each reading, taken from the first line.
+pub fn line_width(input: &str) -> Result<usize, ParseError> {
+    lines(input)
+        .next()
+        .map(|(_, line)| line.chars().count())
+        .ok_or(ParseError::Empty)
+}
+
+/// Parses one binary reading per line, checking that every line has the width of the first.
+pub fn parse_input<W: Word>(input: &str) -> Result<(usize, Vec<W>), ParseError> {
+    encoding::decode_text(input, 2, InputOrder::MostSignificantFirst)
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Rates<W> {
+    pub gamma: W,
+    pub epsilon: W,
+    /// Bit positions set in exactly half of the readings, lowest first.
+    pub ties: Vec<usize>,
+}
+
+/// Fails with `ReportError::Empty` when there are no readings, since every bit would be a tie.
+pub fn calculate_rates<W: Word>(
+    bits: usize,
+    diagnostic_input: &mut dyn Iterator<Item = W>,
+    policy: TiePolicy,
+) -> Result<Rates<W>, ReportError> {
+    check_width::<W>(bits)?;
+    let (total, counters) = count_ones_sliced(bits, diagnostic_input);
+    if total == 0 {
+        return Err(ReportError::Empty);
+    }
+    rates_from_counts(total, &counters, policy).map_err(ReportError::Tie)
+}
+
+/// Gamma and epsilon given how many of `total` readings have each bit set.
+pub(crate) fn rates_from_counts<W: Word>(
+    total: usize,
+    counters: &[usize],
+    policy: TiePolicy,
+) -> Result<Rates<W>, TieError> {
+    rates_from_columns(counters.iter().map(|&counter| (counter, total)), policy)
+}
+
+/// Gamma and epsilon given, for each bit from `0` up, how many readings have it set out of how
+/// many were counted.
+pub(crate) fn rates_from_columns<W: Word>(
+    columns: impl Iterator<Item = (usize, usize)>,
+    policy: TiePolicy,
+) -> Result<Rates<W>, TieError> {
+    let mut epsilon = W::zero();
+    let mut gamma = W::zero();
+    let mut ties = vec![];
+    for (i, (ones, total)) in columns.enumerate() {
+        if ones * 2 == total {
+            ties.push(i);
+        }
+        match policy.most_common(i, ones, total)? {
+            Some(true) => gamma.set_bit(i),
+            Some(false) => epsilon.set_bit(i),
+            None => {}
+        }
+    }
+
+    Ok(Rates {
+        gamma,
+        epsilon,
+        ties,
+    })
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Rating<W> {
+    /// With `TiePolicy::Ambiguous` several readings may survive, in which case this is the first.
+    pub value: W,
+    /// Bit positions where the remaining readings were tied, in the order they were considered.
+    pub ties: Vec<usize>,
+}
+
+pub fn calculate_oxygen_generator<W: Word>(
+    bits: usize,
+    diagnostic_input: Vec<W>,
+    policy: TiePolicy,
+) -> Result<Rating<W>, ReportError> {
+    filter(
+        bits,
+        diagnostic_input,
+        &MostCommon {
+            policy,
+            ..MostCommon::default()
+        },
+    )
+}
+
+pub fn calculate_co2_scrubber<W: Word>(
+    bits: usize,
+    diagnostic_input: Vec<W>,
+    policy: TiePolicy,
+) -> Result<Rating<W>, ReportError> {
+    filter(
+        bits,
+        diagnostic_input,
+        &LeastCommon {
+            policy,
+            ..LeastCommon::default()
+        },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use testing::{example, EXAMPLE};
+
+    #[test]
+    fn example_parse() {
+        let (bits, values) = parse_input::<u32>(EXAMPLE).unwrap();
+
+        assert_eq!(bits, 5);
+        assert_eq!(values, example());
+    }
+
+    #[test]
+    fn example_calculate() {
+        let rates = calculate_rates(5, &mut example().into_iter(), TiePolicy::PreferOne).unwrap();
+
+        assert_eq!(rates.gamma, 22);
+        assert_eq!(rates.epsilon, 9);
+        assert_eq!(rates.gamma.wide_mul(&rates.epsilon), 198);
+        assert_eq!(rates.ties, vec![]);
+    }
+
+    #[test]
+    fn example_calculate_oxygen_generator() {
+        let oxygen_generator =
+            calculate_oxygen_generator(5, example(), TiePolicy::PreferOne).unwrap();
+
+        assert_eq!(oxygen_generator.value, 23);
+        assert_eq!(oxygen_generator.ties, vec![0]);
+    }
+
+    #[test]
+    fn example_calculate_co2_scrubber() {
+        let co2_scrubber = calculate_co2_scrubber(5, example(), TiePolicy::PreferOne).unwrap();
+
+        assert_eq!(co2_scrubber.value, 10);
+        assert_eq!(co2_scrubber.ties, vec![2]);
+    }
+
+    #[test]
+    fn example_every_width() {
+        fn solve<W: Word>() -> (String, String) {
+            let (bits, input) = parse_input::<W>(EXAMPLE).unwrap();
+            let policy = TiePolicy::default();
+            let rates = calculate_rates(bits, &mut input.clone().into_iter(), policy).unwrap();
+            let oxygen_generator = calculate_oxygen_generator(bits, input.clone(), policy).unwrap();
+            let co2_scrubber = calculate_co2_scrubber(bits, input, policy).unwrap();
+            (
+                rates.gamma.wide_mul(&rates.epsilon).to_string(),
+                oxygen_generator
+                    .value
+                    .wide_mul(&co2_scrubber.value)
+                    .to_string(),
+            )
+        }
+
+        let expected = ("198".to_string(), "230".to_string());
+        assert_eq!(solve::<u32>(), expected);
+        assert_eq!(solve::<u64>(), expected);
+        assert_eq!(solve::<u128>(), expected);
+        assert_eq!(solve::<BitSet>(), expected);
+    }
+
+    #[test]
+    fn wide_reports() {
+        let input = format!(
+            "1{}\n0{}\n1{}\n",
+            "0".repeat(99),
+            "1".repeat(99),
+            "1".repeat(99)
+        );
+
+        assert_eq!(Width::detect(line_width(&input).unwrap()), Width::U128);
+        let (bits, values) = parse_input::<u128>(&input).unwrap();
+        let rates = calculate_rates(bits, &mut values.into_iter(), TiePolicy::Error).unwrap();
+        assert_eq!(rates.gamma, u128::MAX >> 28);
+        assert_eq!(rates.epsilon, 0);
+
+        let input = format!("{}\n{}\n", "1".repeat(200), "0".repeat(200));
+        assert_eq!(Width::detect(line_width(&input).unwrap()), Width::BitSet);
+        let (bits, input) = parse_input::<BitSet>(&input).unwrap();
+        let oxygen_generator =
+            calculate_oxygen_generator(bits, input.clone(), TiePolicy::PreferOne)
+                .unwrap()
+                .value;
+        let co2_scrubber = calculate_co2_scrubber(bits, input, TiePolicy::PreferOne)
+            .unwrap()
+            .value;
+        assert_eq!(oxygen_generator.len(), 200);
+        assert!(co2_scrubber.is_empty());
+        assert_eq!(oxygen_generator.wide_mul(&oxygen_generator).len(), 400);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(parse_input::<u32>(""), Err(ParseError::Empty));
+        assert_eq!(parse_input::<u32>("\n  \n"), Err(ParseError::Empty));
+        assert_eq!(
+            parse_input::<u32>("00100\n\n1111\n10110\n"),
+            Err(ParseError::WidthMismatch {
+                line: 3,
+                expected: 5,
+                found: 4
+            })
+        );
+        assert_eq!(
+            parse_input::<u32>("00100\n11110\n10120\n"),
+            Err(ParseError::InvalidCharacter {
+                line: 3,
+                column: 4,
+                character: '2'
+            })
+        );
+        assert_eq!(
+            parse_input::<u32>(&"1".repeat(40)),
+            Err(ParseError::TooWide {
+                line: 1,
+                width: 40,
+                capacity: 32
+            })
+        );
+        assert_eq!(
+            parse_input::<u32>("00100\n1111\n").unwrap_err().to_string(),
+            "line 2: expected 5 bits but found 4"
+        );
+    }
+
+    #[test]
+    fn empty_and_oversized_reports() {
+        let policy = TiePolicy::PreferOne;
+        assert_eq!(
+            calculate_rates::<u32>(5, &mut std::iter::empty(), policy),
+            Err(ReportError::Empty)
+        );
+        assert_eq!(
+            calculate_oxygen_generator::<u32>(5, vec![], policy),
+            Err(ReportError::Empty)
+        );
+        assert_eq!(
+            calculate_co2_scrubber::<u32>(5, vec![], policy),
+            Err(ReportError::Empty)
+        );
+
+        let too_wide = ReportError::TooWide {
+            bits: 40,
+            capacity: 32,
+        };
+        assert_eq!(
+            calculate_rates(40, &mut example().into_iter(), policy),
+            Err(too_wide)
+        );
+        assert_eq!(
+            calculate_oxygen_generator(40, example(), policy),
+            Err(too_wide)
+        );
+        assert_eq!(too_wide.to_string(), "40 bits do not fit in a 32-bit word");
+    }
+
+    #[test]
+    fn tie_policies() {
+        let input: Vec<u32> = vec![0b10, 0b11, 0b00, 0b01, 0b11];
+
+        let rates = calculate_rates(2, &mut input[..4].iter().copied(), TiePolicy::PreferOne);
+        assert_eq!(
+            rates,
+            Ok(Rates {
+                gamma: 0b11,
+                epsilon: 0b00,
+                ties: vec![0, 1]
+            })
+        );
+        let rates = calculate_rates(2, &mut input[..4].iter().copied(), TiePolicy::PreferZero);
+        let rates = rates.unwrap();
+        assert_eq!((rates.gamma, rates.epsilon), (0b00, 0b11));
+        let rates = calculate_rates(2, &mut input.iter().copied(), TiePolicy::Ambiguous).unwrap();
+        assert_eq!((rates.gamma, rates.epsilon), (0b11, 0b00));
+        assert_eq!(
+            calculate_rates(2, &mut input[..4].iter().copied(), TiePolicy::Error),
+            Err(ReportError::Tie(TieError { bit: 0 }))
+        );
+        let rates =
+            calculate_rates(2, &mut input[..4].iter().copied(), TiePolicy::Ambiguous).unwrap();
+        assert_eq!((rates.gamma, rates.epsilon), (0b00, 0b00));
+
+        let oxygen_generator =
+            calculate_oxygen_generator(2, input[..4].to_vec(), TiePolicy::PreferZero).unwrap();
+        assert_eq!(oxygen_generator.value, 0b00);
+        assert_eq!(oxygen_generator.ties, vec![1, 0]);
+        let co2_scrubber = calculate_co2_scrubber(2, input[..4].to_vec(), TiePolicy::PreferZero);
+        assert_eq!(co2_scrubber.unwrap().value, 0b11);
+        assert_eq!(
+            calculate_co2_scrubber(2, input[..4].to_vec(), TiePolicy::Error),
+            Err(ReportError::Tie(TieError { bit: 1 }))
+        );
+
+        let co2_scrubber =
+            calculate_co2_scrubber(2, input[..4].to_vec(), TiePolicy::Ambiguous).unwrap();
+        assert_eq!(co2_scrubber.value, 0b10);
+        assert_eq!(co2_scrubber.ties, vec![1, 0]);
+    }
+}
@@ -1,100 +1,54 @@
-use std::{fs::File, io::Read};
-
-fn parse_input<'a>(input: &'a str) -> (usize, impl Iterator<Item = u16> + 'a) {
-    let mut line_iter = input
-        .lines()
-        .filter_map(|line| {
-            let line = line.trim();
-            if line.len() > 0 {
-                Some(line)
-            } else {
-                None
-            }
-        })
-        .peekable();
-
-    let bits = line_iter.peek().unwrap().len();
-
-    (bits, line_iter.map(|x| u16::from_str_radix(x, 2).unwrap()))
-}
-
-fn calculate_rates(bits: usize, diagnostic_input: &mut dyn Iterator<Item = u16>) -> (u16, u16) {
-    let mut counters = vec![0usize; bits];
-    let mut total = 0;
-    for value in diagnostic_input {
-        total += 1;
-        for i in 0..bits {
-            if value & (1 << i) != 0 {
-                counters[i] += 1;
-            }
+use day03_part1::{
+    cli::Options,
+    encoding::{decode, Encoding},
+    *,
+};
+
+fn print_power_consumption<W: Word>(
+    contents: &[u8],
+    options: &Options,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Options {
+        encoding, order, ..
+    } = *options;
+    let statistics = options.flag("--stats");
+    let rates = if encoding == Encoding::Binary && contents.contains(&(masked::UNKNOWN as u8)) {
+        if statistics {
+            return Err("statistics need every bit of every reading to be known".into());
         }
-    }
-
-    let mut epsilon = 0;
-    let mut gamma = 0;
-    for i in 0..bits {
-        if counters[i] > total / 2 {
-            gamma |= 1 << i;
-        } else {
-            epsilon |= 1 << i;
+        let (bits, diagnostic_input) =
+            masked::parse_masked::<W>(&String::from_utf8_lossy(contents), order)?;
+        masked::calculate_rates(bits, &diagnostic_input, TiePolicy::PreferOne)?
+    } else {
+        let (bits, diagnostic_input) = decode::<W>(contents, encoding, order)?;
+        if statistics {
+            print!("{}", stats::analyse(bits, &diagnostic_input)?);
         }
+        calculate_rates(
+            bits,
+            &mut diagnostic_input.into_iter(),
+            TiePolicy::PreferOne,
+        )?
+    };
+
+    if !rates.ties.is_empty() {
+        println!("Tied bits: {:?}", rates.ties);
     }
-
-    (gamma, epsilon)
-}
-
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut contents = String::new();
-    {
-        let mut file = File::open("./day03/part1/input.txt")?;
-        file.read_to_string(&mut contents)?;
-    }
-
-    let (bits, mut diagnostic_input) = parse_input(&contents);
-    let (gamma, epsilon) = calculate_rates(bits, &mut diagnostic_input);
-
-    println!("Power consumption {}", gamma as u32 * epsilon as u32);
+    println!("Power consumption {}", rates.gamma.wide_mul(&rates.epsilon));
 
     Ok(())
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
-
-    #[test]
-    fn example_parse() {
-        let input = "
-            00100
-            11110
-            10110
-            10111
-            10101
-            01111
-            00111
-            11100
-            10000
-            11001
-            00010
-            01010
-        ";
-
-        let (bits, iter) = parse_input(&input);
-
-        assert_eq!(bits, 5);
-        assert_eq!(
-            iter.collect::<Vec<_>>(),
-            vec![4, 30, 22, 23, 21, 15, 7, 28, 16, 25, 2, 10],
-        );
-    }
-
-    #[test]
-    fn example_calculate() {
-        let input = vec![4, 30, 22, 23, 21, 15, 7, 28, 16, 25, 2, 10];
-
-        let (gamma, epsilon) = calculate_rates(5, &mut input.into_iter());
-
-        assert_eq!(gamma, 22);
-        assert_eq!(epsilon, 9);
-    }
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let options = Options::parse(std::env::args().skip(1), &["--stats"])?;
+    let path = options
+        .paths
+        .last()
+        .map_or("./day03/part1/input.txt", String::as_str);
+    let contents = std::fs::read(path)?;
+
+    with_word!(
+        Width::detect(options.encoding.width(&contents)?),
+        print_power_consumption(&contents, &options)
+    )
 }
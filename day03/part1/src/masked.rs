@@ -0,0 +1,279 @@
+use crate::{
+    check_width,
+    encoding::{parse_lines, InputOrder},
+    filter::{run, Criteria, LeastCommon, MostCommon},
+    rates_from_columns, ParseError, Rates, Rating, ReportError, TiePolicy, Word,
+};
+
+/// The symbol for a bit whose value is not known.
+pub const UNKNOWN: char = '?';
+
+/// A reading in which some bits may be unknown.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Masked<W> {
+    /// The known bits that are set; unknown bits are always unset.
+    pub value: W,
+    /// Set for every bit whose value is known.
+    pub known: W,
+}
+
+impl<W: Word> Masked<W> {
+    /// A reading whose low `bits` bits are all known.
+    pub fn exact(value: W, bits: usize) -> Self {
+        let mut known = W::zero();
+        for bit in 0..bits {
+            known.set_bit(bit);
+        }
+        Masked { value, known }
+    }
+
+    /// The value of the low `bits` bits, or `None` when any of them is unknown.
+    pub fn known_value(&self, bits: usize) -> Option<&W> {
+        if (0..bits).all(|bit| self.known.bit(bit)) {
+            Some(&self.value)
+        } else {
+            None
+        }
+    }
+
+    /// The value of `index`, or `None` when it is unknown.
+    pub fn bit(&self, index: usize) -> Option<bool> {
+        if self.known.bit(index) {
+            Some(self.value.bit(index))
+        } else {
+            None
+        }
+    }
+}
+
+/// Formats the low `bits` bits of `reading`, showing unknown bits as `?`.
+pub fn format_masked<W: Word>(reading: &Masked<W>, bits: usize) -> String {
+    (0..bits)
+        .rev()
+        .map(|bit| match reading.bit(bit) {
+            Some(true) => '1',
+            Some(false) => '0',
+            None => UNKNOWN,
+        })
+        .collect()
+}
+
+/// How the rating filters treat readings whose value at the current bit is unknown. Either way
+/// they are left out of the counts that decide which value is wanted.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Unknowns {
+    /// Keep them, since the unknown bit could have the wanted value. Several readings may then
+    /// survive every position.
+    #[default]
+    Keep,
+    /// Filter them out, since the unknown bit cannot be shown to have the wanted value.
+    Discard,
+}
+
+/// Parses one reading per line of `0`, `1` and `?`, checking widths as `parse_input` does, with
+/// the first character of a line holding the bit `order` puts first.
+pub fn parse_masked<W: Word>(
+    input: &str,
+    order: InputOrder,
+) -> Result<(usize, Vec<Masked<W>>), ParseError> {
+    parse_lines::<W, _, _>(
+        input,
+        1,
+        order,
+        |character| match character {
+            '0' => Some(Some(false)),
+            '1' => Some(Some(true)),
+            UNKNOWN => Some(None),
+            _ => None,
+        },
+        |digits| {
+            let mut reading = Masked {
+                value: W::zero(),
+                known: W::zero(),
+            };
+            for &(index, digit) in digits {
+                if let Some(value) = digit {
+                    reading.known.set_bit(index);
+                    if value {
+                        reading.value.set_bit(index);
+                    }
+                }
+            }
+            reading
+        },
+    )
+}
+
+/// Like `crate::calculate_rates`, with each bit decided only by the readings where it is known.
+/// A bit unknown in every reading counts as a tie.
+pub fn calculate_rates<W: Word>(
+    bits: usize,
+    diagnostic_input: &[Masked<W>],
+    policy: TiePolicy,
+) -> Result<Rates<W>, ReportError> {
+    check_width::<W>(bits)?;
+    if diagnostic_input.is_empty() {
+        return Err(ReportError::Empty);
+    }
+    let mut columns = vec![(0, 0); bits];
+    for reading in diagnostic_input {
+        for (bit, (ones, total)) in columns.iter_mut().enumerate() {
+            if let Some(value) = reading.bit(bit) {
+                *ones += value as usize;
+                *total += 1;
+            }
+        }
+    }
+    rates_from_columns(columns.into_iter(), policy).map_err(ReportError::Tie)
+}
+
+/// Like `filter::filter`, with `criteria` seeing only the readings whose current bit is known and
+/// `unknowns` deciding the fate of the rest. A position where no known value matches the wanted
+/// one filters nothing out.
+pub fn filter<W: Word, C: Criteria + ?Sized>(
+    bits: usize,
+    diagnostic_input: Vec<Masked<W>>,
+    criteria: &C,
+    unknowns: Unknowns,
+) -> Result<Rating<Masked<W>>, ReportError> {
+    check_width::<W>(bits)?;
+    run(
+        bits,
+        diagnostic_input,
+        criteria,
+        Masked::bit,
+        unknowns == Unknowns::Keep,
+        None,
+    )
+}
+
+pub fn calculate_oxygen_generator<W: Word>(
+    bits: usize,
+    diagnostic_input: Vec<Masked<W>>,
+    policy: TiePolicy,
+    unknowns: Unknowns,
+) -> Result<Rating<Masked<W>>, ReportError> {
+    filter(
+        bits,
+        diagnostic_input,
+        &MostCommon {
+            policy,
+            ..MostCommon::default()
+        },
+        unknowns,
+    )
+}
+
+pub fn calculate_co2_scrubber<W: Word>(
+    bits: usize,
+    diagnostic_input: Vec<Masked<W>>,
+    policy: TiePolicy,
+    unknowns: Unknowns,
+) -> Result<Rating<Masked<W>>, ReportError> {
+    filter(
+        bits,
+        diagnostic_input,
+        &LeastCommon {
+            policy,
+            ..LeastCommon::default()
+        },
+        unknowns,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::EXAMPLE;
+
+    #[test]
+    fn example_fully_known() {
+        let (bits, readings) =
+            parse_masked::<u32>(EXAMPLE, InputOrder::MostSignificantFirst).unwrap();
+        let (_, values) = crate::parse_input::<u32>(EXAMPLE).unwrap();
+        let policy = TiePolicy::PreferOne;
+
+        assert_eq!(
+            readings,
+            values
+                .iter()
+                .map(|&value| Masked::exact(value, bits))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            calculate_rates(bits, &readings, policy),
+            crate::calculate_rates(bits, &mut values.clone().into_iter(), policy)
+        );
+        for unknowns in [Unknowns::Keep, Unknowns::Discard] {
+            let oxygen_generator =
+                calculate_oxygen_generator(bits, readings.clone(), policy, unknowns).unwrap();
+            assert_eq!(oxygen_generator.value.value, 23);
+            assert_eq!(oxygen_generator.ties, vec![0]);
+            let co2_scrubber =
+                calculate_co2_scrubber(bits, readings.clone(), policy, unknowns).unwrap();
+            assert_eq!(co2_scrubber.value.value, 10);
+        }
+    }
+
+    #[test]
+    fn unknown_bits() {
+        let input = "10?01\n11?00\n0?001\n?1111\n";
+        let (bits, readings) =
+            parse_masked::<u32>(input, InputOrder::MostSignificantFirst).unwrap();
+
+        assert_eq!(bits, 5);
+        assert_eq!(readings[0].bit(2), None);
+        assert_eq!(readings[0].bit(4), Some(true));
+        assert_eq!(format_masked(&readings[2], bits), "0?001");
+        assert_eq!(readings[2].known_value(bits), None);
+        assert_eq!(readings[2].known_value(3), Some(&0b001));
+        let (_, reversed) =
+            parse_masked::<u32>("0?001\n", InputOrder::LeastSignificantFirst).unwrap();
+        assert_eq!(format_masked(&reversed[0], bits), "100?0");
+
+        let rates = calculate_rates(bits, &readings, TiePolicy::PreferZero).unwrap();
+        assert_eq!(rates.gamma, 0b11001);
+        assert_eq!(rates.epsilon, 0b00110);
+        assert_eq!(rates.ties, vec![2]);
+    }
+
+    #[test]
+    fn filter_policies() {
+        let (bits, readings) =
+            parse_masked::<u32>("100\n1?1\n010\n110\n", InputOrder::MostSignificantFirst).unwrap();
+        let policy = TiePolicy::PreferOne;
+
+        // Bit 2 keeps the three readings starting with `1`. Of those, bit 1 is known in two
+        // and tied, so `1` is wanted: the reading with `?` survives only when kept.
+        let kept = calculate_oxygen_generator(bits, readings.clone(), policy, Unknowns::Keep);
+        let kept = kept.unwrap();
+        assert_eq!(kept.ties, vec![1, 0]);
+        assert_eq!(format_masked(&kept.value, bits), "1?1");
+        let discarded =
+            calculate_oxygen_generator(bits, readings.clone(), policy, Unknowns::Discard);
+        assert_eq!(format_masked(&discarded.unwrap().value, bits), "110");
+
+        let co2_scrubber = calculate_co2_scrubber(bits, readings, policy, Unknowns::Discard);
+        assert_eq!(format_masked(&co2_scrubber.unwrap().value, bits), "010");
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            parse_masked::<u32>("10?01\n10*01\n", InputOrder::MostSignificantFirst),
+            Err(ParseError::InvalidCharacter {
+                line: 2,
+                column: 3,
+                character: '*'
+            })
+        );
+        assert_eq!(
+            parse_masked::<u32>("10?01\n1?\n", InputOrder::MostSignificantFirst),
+            Err(ParseError::WidthMismatch {
+                line: 2,
+                expected: 5,
+                found: 2
+            })
+        );
+    }
+}
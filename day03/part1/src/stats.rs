@@ -0,0 +1,280 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{check_width, counting::count_ones_sliced, list_readings, ReportError, Word};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BitStatistics {
+    pub bit: usize,
+    pub ones: usize,
+    /// Fraction of readings with the bit set.
+    pub ratio: f64,
+    /// Shannon entropy of the bit in bits: `0` for a constant bit, `1` for an even split.
+    pub entropy: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statistics<W> {
+    pub width: usize,
+    pub total: usize,
+    /// Indexed by bit position.
+    pub bits: Vec<BitStatistics>,
+    /// Each distinct reading with how many times it occurs, in order of first appearance.
+    distinct: Vec<(W, usize)>,
+    /// Every reading sharing the highest count, in order of first appearance, with that count.
+    pub most_frequent: (Vec<W>, usize),
+    /// Every reading sharing the lowest count, in order of first appearance, with that count.
+    pub least_frequent: (Vec<W>, usize),
+}
+
+fn entropy(ratio: f64) -> f64 {
+    [ratio, 1.0 - ratio]
+        .iter()
+        .filter(|&&p| p > 0.0)
+        .map(|&p| -p * p.log2())
+        .sum()
+}
+
+/// Gathers per-bit and per-reading statistics over a report `width` bits wide.
+///
+/// Fails with `ReportError::TooWide` when `W` cannot hold `width` bits.
+pub fn analyse<W: Word>(width: usize, readings: &[W]) -> Result<Statistics<W>, ReportError> {
+    check_width::<W>(width)?;
+    let (total, ones) = count_ones_sliced(width, &mut readings.iter().cloned());
+    let mut indices: HashMap<&W, usize> = HashMap::new();
+    let mut distinct: Vec<(W, usize)> = vec![];
+    for value in readings {
+        match indices.get(value) {
+            Some(&index) => distinct[index].1 += 1,
+            None => {
+                indices.insert(value, distinct.len());
+                distinct.push((value.clone(), 1));
+            }
+        }
+    }
+
+    let bits = ones
+        .into_iter()
+        .enumerate()
+        .map(|(bit, ones)| {
+            let ratio = if total == 0 {
+                0.0
+            } else {
+                ones as f64 / total as f64
+            };
+            BitStatistics {
+                bit,
+                ones,
+                ratio,
+                entropy: entropy(ratio),
+            }
+        })
+        .collect();
+
+    let extreme = |count: Option<usize>| {
+        let count = count.unwrap_or(0);
+        let values = distinct
+            .iter()
+            .filter(|&&(_, c)| c == count)
+            .map(|(value, _)| value.clone())
+            .collect();
+        (values, count)
+    };
+    let most_frequent = extreme(distinct.iter().map(|&(_, c)| c).max());
+    let least_frequent = extreme(distinct.iter().map(|&(_, c)| c).min());
+
+    Ok(Statistics {
+        width,
+        total,
+        bits,
+        distinct,
+        most_frequent,
+        least_frequent,
+    })
+}
+
+impl<W: Word> Statistics<W> {
+    /// Bits that are set in every reading or in none, the signature of a stuck sensor.
+    pub fn stuck_bits(&self) -> Vec<usize> {
+        self.bits
+            .iter()
+            .filter(|stats| stats.ones == 0 || stats.ones == self.total)
+            .map(|stats| stats.bit)
+            .collect()
+    }
+
+    /// The phi coefficient between bits `i` and `j`, from `-1` when they always differ to `1`
+    /// when they always agree, or `None` when either bit is constant or outside the report.
+    ///
+    /// Readings with both bits set are counted on each call, over the distinct readings.
+    pub fn correlation(&self, i: usize, j: usize) -> Option<f64> {
+        if i >= self.width || j >= self.width {
+            return None;
+        }
+        let both = self
+            .distinct
+            .iter()
+            .filter(|(value, _)| value.bit(i) && value.bit(j))
+            .map(|&(_, count)| count)
+            .sum();
+        self.phi(i, j, both)
+    }
+
+    /// The phi coefficient between bits `i` and `j` given how many readings have both set.
+    fn phi(&self, i: usize, j: usize, both: usize) -> Option<f64> {
+        let n = self.total as f64;
+        let (ones_i, ones_j) = (self.bits.get(i)?.ones as f64, self.bits.get(j)?.ones as f64);
+        let both = both as f64;
+
+        let spread = ones_i * (n - ones_i) * ones_j * (n - ones_j);
+        if spread == 0.0 {
+            return None;
+        }
+        Some((n * both - ones_i * ones_j) / spread.sqrt())
+    }
+
+    /// The pair of distinct bits with the largest correlation in magnitude, with its value.
+    ///
+    /// Readings with both bits set are counted for every pair in a single pass over the distinct
+    /// readings, visiting only the pairs of bits each one has set.
+    pub fn strongest_correlation(&self) -> Option<((usize, usize), f64)> {
+        let mut both = vec![0; self.width * self.width];
+        for (value, count) in &self.distinct {
+            let set: Vec<usize> = (0..self.width).filter(|&bit| value.bit(bit)).collect();
+            for (index, &i) in set.iter().enumerate() {
+                for &j in &set[index + 1..] {
+                    both[i * self.width + j] += count;
+                }
+            }
+        }
+
+        (0..self.width)
+            .flat_map(|i| (i + 1..self.width).map(move |j| (i, j)))
+            .filter_map(|(i, j)| Some(((i, j), self.phi(i, j, both[i * self.width + j])?)))
+            .max_by(|(_, left), (_, right)| left.abs().total_cmp(&right.abs()))
+    }
+}
+
+impl<W: Word> fmt::Display for Statistics<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} readings of {} bits", self.total, self.width)?;
+        writeln!(
+            f,
+            "{:>4} {:>8} {:>7} {:>7}",
+            "bit", "ones", "ratio", "entropy"
+        )?;
+        for stats in self.bits.iter().rev() {
+            writeln!(
+                f,
+                "{:>4} {:>8} {:>7.3} {:>7.3}",
+                stats.bit, stats.ones, stats.ratio, stats.entropy
+            )?;
+        }
+
+        let stuck = self.stuck_bits();
+        if !stuck.is_empty() {
+            writeln!(f, "Stuck bits: {:?}", stuck)?;
+        }
+
+        if let Some(((i, j), correlation)) = self.strongest_correlation() {
+            writeln!(
+                f,
+                "Strongest correlation: bits {} and {} ({:.3})",
+                i, j, correlation
+            )?;
+        }
+
+        for (name, (values, count)) in [
+            ("Most", &self.most_frequent),
+            ("Least", &self.least_frequent),
+        ] {
+            writeln!(
+                f,
+                "{} frequent ({} each): {}",
+                name,
+                count,
+                list_readings(values, self.width)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::example;
+
+    #[test]
+    fn example_bits() {
+        let stats = analyse(5, &example()).unwrap();
+
+        assert_eq!(
+            stats.bits.iter().map(|bit| bit.ones).collect::<Vec<_>>(),
+            vec![5, 7, 8, 5, 7]
+        );
+        assert!((stats.bits[4].ratio - 7.0 / 12.0).abs() < 1e-12);
+        assert!((stats.bits[4].entropy - 0.979_868_756_651_152_8).abs() < 1e-12);
+        assert_eq!(stats.stuck_bits(), vec![]);
+        assert_eq!(stats.most_frequent, (example(), 1));
+    }
+
+    #[test]
+    fn stuck_bits_and_frequencies() {
+        let readings: Vec<u32> = vec![0b101, 0b100, 0b101, 0b111, 0b100, 0b101];
+
+        let stats = analyse(3, &readings).unwrap();
+
+        assert_eq!(stats.stuck_bits(), vec![2]);
+        assert_eq!(stats.bits[2].entropy, 0.0);
+        assert_eq!(stats.most_frequent, (vec![0b101], 3));
+        assert_eq!(stats.least_frequent, (vec![0b111], 1));
+        assert_eq!(stats.correlation(0, 2), None);
+        assert!(stats.to_string().contains("Stuck bits: [2]\n"));
+        assert!(stats
+            .to_string()
+            .ends_with("Most frequent (3 each): 101\nLeast frequent (1 each): 111\n"));
+    }
+
+    #[test]
+    fn correlations() {
+        let readings: Vec<u32> = vec![0b011, 0b100, 0b011, 0b100, 0b111, 0b000];
+
+        let stats = analyse(3, &readings).unwrap();
+
+        assert!((stats.correlation(0, 1).unwrap() - 1.0).abs() < 1e-12);
+        assert!((stats.correlation(1, 0).unwrap() - 1.0).abs() < 1e-12);
+        assert!((stats.correlation(0, 2).unwrap() + 1.0 / 3.0).abs() < 1e-12);
+        assert!((stats.correlation(2, 2).unwrap() - 1.0).abs() < 1e-12);
+        assert_eq!(stats.strongest_correlation().unwrap().0, (0, 1));
+        assert_eq!(stats.correlation(0, 3), None);
+        assert_eq!(stats.correlation(usize::MAX, 1), None);
+    }
+
+    #[test]
+    fn strongest_correlation_matches_pairwise() {
+        let readings: Vec<u32> = vec![0b1011, 0b0100, 0b0011, 0b1101, 0b0111, 0b0000, 0b1001];
+
+        let stats = analyse(4, &readings).unwrap();
+
+        let ((i, j), strongest) = stats.strongest_correlation().unwrap();
+        assert_eq!(stats.correlation(i, j), Some(strongest));
+        for i in 0..4 {
+            for j in i + 1..4 {
+                assert!(stats
+                    .correlation(i, j)
+                    .is_none_or(|correlation| correlation.abs() <= strongest.abs()));
+            }
+        }
+    }
+
+    #[test]
+    fn too_wide() {
+        assert_eq!(
+            analyse::<u32>(40, &[1]),
+            Err(ReportError::TooWide {
+                bits: 40,
+                capacity: 32
+            })
+        );
+    }
+}
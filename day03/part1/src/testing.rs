@@ -0,0 +1,33 @@
+//! Fixtures shared by the tests of every module and by the benchmarks.
+
+/// The report from the puzzle description.
+pub const EXAMPLE: &str = "
+    00100
+    11110
+    10110
+    10111
+    10101
+    01111
+    00111
+    11100
+    10000
+    11001
+    00010
+    01010
+";
+
+/// The readings of `EXAMPLE`.
+pub fn example() -> Vec<u32> {
+    vec![4, 30, 22, 23, 21, 15, 7, 28, 16, 25, 2, 10]
+}
+
+/// An endless xorshift sequence, the same every time for the same nonzero `seed`.
+pub fn xorshift(seed: u64) -> impl Iterator<Item = u64> {
+    let mut state = seed;
+    std::iter::repeat_with(move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    })
+}
@@ -0,0 +1,50 @@
+use std::fmt;
+
+/// How to pick the most common bit when exactly half of the readings have it set.
+///
+/// The least common bit is always the opposite of the most common one, so `PreferOne` gives the
+/// puzzle's rules: oxygen keeps `1` on a tie and CO2 keeps `0`.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum TiePolicy {
+    #[default]
+    PreferOne,
+    PreferZero,
+    /// Fail with a `TieError`.
+    Error,
+    /// Leave the bit undecided: unset in both rates, and no readings filtered out on it.
+    Ambiguous,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct TieError {
+    pub bit: usize,
+}
+
+impl fmt::Display for TieError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bit {} is set in exactly half of the readings", self.bit)
+    }
+}
+
+impl std::error::Error for TieError {}
+
+impl TiePolicy {
+    /// The most common value of `bit` when `ones` of `total` readings have it set, or `None`
+    /// for an ambiguous tie.
+    pub fn most_common(
+        self,
+        bit: usize,
+        ones: usize,
+        total: usize,
+    ) -> Result<Option<bool>, TieError> {
+        if ones * 2 != total {
+            return Ok(Some(ones * 2 > total));
+        }
+        match self {
+            TiePolicy::PreferOne => Ok(Some(true)),
+            TiePolicy::PreferZero => Ok(Some(false)),
+            TiePolicy::Error => Err(TieError { bit }),
+            TiePolicy::Ambiguous => Ok(None),
+        }
+    }
+}
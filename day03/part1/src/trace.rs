@@ -0,0 +1,97 @@
+use std::fmt;
+
+use crate::{list_readings, Word};
+
+/// What a rating filter did at one bit position.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TraceStep<W> {
+    pub bit: usize,
+    pub ones: usize,
+    pub zeros: usize,
+    /// The value readings had to have to survive, or `None` when nothing was filtered out.
+    pub kept: Option<bool>,
+    pub eliminated: Vec<W>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Trace<W> {
+    pub bits: usize,
+    pub steps: Vec<TraceStep<W>>,
+}
+
+impl<W: Word> fmt::Display for Trace<W> {
+    /// Renders the trace as a table with one row per bit position considered.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>4} {:>8} {:>8} {:>4}  eliminated",
+            "bit", "ones", "zeros", "keep"
+        )?;
+        for step in &self.steps {
+            let kept = match step.kept {
+                Some(true) => "1",
+                Some(false) => "0",
+                None => "-",
+            };
+            let eliminated = list_readings(&step.eliminated, self.bits);
+
+            write!(
+                f,
+                "{:>4} {:>8} {:>8} {:>4}",
+                step.bit, step.ones, step.zeros, kept
+            )?;
+            if !eliminated.is_empty() {
+                write!(f, "  {}", eliminated)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn render_table() {
+        let trace: Trace<u32> = Trace {
+            bits: 5,
+            steps: vec![
+                TraceStep {
+                    bit: 4,
+                    ones: 2,
+                    zeros: 10,
+                    kept: Some(false),
+                    eliminated: vec![30, 22],
+                },
+                TraceStep {
+                    bit: 3,
+                    ones: 5,
+                    zeros: 5,
+                    kept: None,
+                    eliminated: vec![],
+                },
+                TraceStep {
+                    bit: 2,
+                    ones: 1,
+                    zeros: 9,
+                    kept: Some(false),
+                    eliminated: (0..9).collect(),
+                },
+            ],
+        };
+
+        assert_eq!(
+            trace.to_string(),
+            [
+                " bit     ones    zeros keep  eliminated",
+                "   4        2       10    0  11110 10110",
+                "   3        5        5    -",
+                "   2        1        9    0  00000 00001 00010 00011 00100 00101 00110 00111 ... (1 more)",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+}
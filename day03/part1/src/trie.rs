@@ -0,0 +1,324 @@
+use std::marker::PhantomData;
+
+use crate::{
+    check_width,
+    filter::{BitOrder, Criteria, LeastCommon, MostCommon},
+    Rating, ReportError, TiePolicy, Word,
+};
+
+const ROOT: usize = 0;
+/// Child index meaning "no child"; the root is never anyone's child.
+const NONE: usize = 0;
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    count: usize,
+    children: [usize; 2],
+}
+
+/// A binary trie over the readings of a report, built once so that every rating query is a walk
+/// down subtree counts instead of a fresh filter over all readings.
+///
+/// Building the trie costs more than filtering for both ratings, so it only pays off when the
+/// same readings are queried repeatedly or change a few at a time, not for a single answer.
+///
+/// Levels follow the bit order the trie was built with, and the trie holds a node per distinct
+/// prefix, so very wide reports with few shared prefixes are better served by `filter::filter`.
+#[derive(Debug, Clone)]
+pub struct Trie<W> {
+    order: BitOrder,
+    /// The bit position tested at each level.
+    positions: Vec<usize>,
+    nodes: Vec<Node>,
+    word: PhantomData<W>,
+}
+
+impl<W: Word> Trie<W> {
+    /// Fails with `ReportError::TooWide` when `W` cannot hold `bits` bits.
+    pub fn new(bits: usize, diagnostic_input: &[W], order: BitOrder) -> Result<Self, ReportError> {
+        check_width::<W>(bits)?;
+        let mut trie = Trie {
+            order,
+            positions: order.positions(bits).collect(),
+            nodes: vec![Node::default()],
+            word: PhantomData,
+        };
+        for value in diagnostic_input {
+            trie.insert(value);
+        }
+        Ok(trie)
+    }
+
+    pub fn insert(&mut self, value: &W) {
+        let mut node = ROOT;
+        self.nodes[node].count += 1;
+        for &bit in &self.positions {
+            let branch = value.bit(bit) as usize;
+            if self.nodes[node].children[branch] == NONE {
+                self.nodes.push(Node::default());
+                self.nodes[node].children[branch] = self.nodes.len() - 1;
+            }
+            node = self.nodes[node].children[branch];
+            self.nodes[node].count += 1;
+        }
+    }
+
+    /// Removes one occurrence of `value`, returning whether it was present.
+    pub fn remove(&mut self, value: &W) -> bool {
+        let mut path = vec![ROOT];
+        for &bit in &self.positions {
+            let node = path[path.len() - 1];
+            let branch = value.bit(bit) as usize;
+            if self.count(node, branch) == 0 {
+                return false;
+            }
+            path.push(self.nodes[node].children[branch]);
+        }
+
+        for node in path {
+            self.nodes[node].count -= 1;
+        }
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes[ROOT].count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn count(&self, node: usize, branch: usize) -> usize {
+        match self.nodes[node].children[branch] {
+            NONE => 0,
+            child => self.nodes[child].count,
+        }
+    }
+
+    /// Answers the same question as `filter::filter` with the same criteria.
+    ///
+    /// When a tie is left ambiguous the surviving readings can span several subtrees; the
+    /// result is then the first of them in trie order rather than in input order.
+    ///
+    /// Fails with `ReportError::Empty` if the trie is empty, and with `ReportError::OrderMismatch`
+    /// if the criteria consider bits in a different order than the trie was built with.
+    pub fn query<C: Criteria + ?Sized>(&self, criteria: &C) -> Result<Rating<W>, ReportError> {
+        if criteria.order() != self.order {
+            return Err(ReportError::OrderMismatch {
+                expected: self.order,
+                found: criteria.order(),
+            });
+        }
+        if self.is_empty() {
+            return Err(ReportError::Empty);
+        }
+
+        let mut frontier = vec![(ROOT, W::zero())];
+        let mut ties = vec![];
+        let mut level = 0;
+
+        while level < self.positions.len() {
+            let total: usize = frontier
+                .iter()
+                .map(|&(node, _)| self.nodes[node].count)
+                .sum();
+            if total == 1 {
+                break;
+            }
+
+            let bit = self.positions[level];
+            let ones: usize = frontier.iter().map(|&(node, _)| self.count(node, 1)).sum();
+            if ones * 2 == total {
+                ties.push(bit);
+            }
+            let wanted = match criteria.keep(bit, ones, total).map_err(ReportError::Tie)? {
+                Some(true) if ones == 0 => None,
+                Some(false) if ones == total => None,
+                wanted => wanted,
+            };
+
+            let mut next = vec![];
+            for (node, prefix) in frontier {
+                for branch in [false, true] {
+                    let child = self.nodes[node].children[branch as usize];
+                    if self.count(node, branch as usize) > 0
+                        && wanted.is_none_or(|wanted| wanted == branch)
+                    {
+                        let mut prefix = prefix.clone();
+                        if branch {
+                            prefix.set_bit(bit);
+                        }
+                        next.push((child, prefix));
+                    }
+                }
+            }
+            frontier = next;
+            level += 1;
+        }
+
+        let (mut node, mut value) = frontier.swap_remove(0);
+        for &bit in &self.positions[level..] {
+            let branch = self.count(node, 0) == 0;
+            if branch {
+                value.set_bit(bit);
+            }
+            node = self.nodes[node].children[branch as usize];
+        }
+
+        Ok(Rating { value, ties })
+    }
+
+    pub fn oxygen_generator(&self, policy: TiePolicy) -> Result<Rating<W>, ReportError> {
+        self.query(&MostCommon {
+            policy,
+            order: self.order,
+        })
+    }
+
+    pub fn co2_scrubber(&self, policy: TiePolicy) -> Result<Rating<W>, ReportError> {
+        self.query(&LeastCommon {
+            policy,
+            order: self.order,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        filter::{filter, Predicate},
+        testing::{example, xorshift},
+    };
+
+    fn random_readings(seed: u64, bits: usize, length: usize) -> Vec<u32> {
+        xorshift(seed)
+            .map(|state| (state % (1 << bits)) as u32)
+            .take(length)
+            .collect()
+    }
+
+    #[test]
+    fn example_ratings() {
+        let trie = Trie::new(5, &example(), BitOrder::MostSignificantFirst).unwrap();
+
+        assert_eq!(trie.len(), 12);
+        let oxygen_generator = trie.oxygen_generator(TiePolicy::PreferOne).unwrap();
+        assert_eq!(oxygen_generator.value, 23);
+        assert_eq!(oxygen_generator.ties, vec![0]);
+        let co2_scrubber = trie.co2_scrubber(TiePolicy::PreferOne).unwrap();
+        assert_eq!(co2_scrubber.value, 10);
+        assert_eq!(co2_scrubber.ties, vec![2]);
+    }
+
+    #[test]
+    fn matches_filter() {
+        for seed in 1..20 {
+            let bits = 2 + seed as usize % 9;
+            let readings = random_readings(seed, bits, 1 + seed as usize * 7);
+
+            for order in [
+                BitOrder::MostSignificantFirst,
+                BitOrder::LeastSignificantFirst,
+            ] {
+                let trie = Trie::new(bits, &readings, order).unwrap();
+                for policy in [
+                    TiePolicy::PreferOne,
+                    TiePolicy::PreferZero,
+                    TiePolicy::Error,
+                ] {
+                    let most = MostCommon { policy, order };
+                    let least = LeastCommon { policy, order };
+                    assert_eq!(
+                        trie.query(&most),
+                        filter(bits, readings.clone(), &most),
+                        "{} {:?} {:?}",
+                        seed,
+                        order,
+                        policy
+                    );
+                    assert_eq!(trie.query(&least), filter(bits, readings.clone(), &least));
+                }
+
+                let predicate = Predicate {
+                    keep: |bit, ones, total| {
+                        Some(if bit % 2 == 0 {
+                            ones > 0
+                        } else {
+                            ones == total
+                        })
+                    },
+                    order,
+                };
+                assert_eq!(
+                    trie.query(&predicate),
+                    filter(bits, readings.clone(), &predicate)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn ambiguous_ties_keep_every_subtree() {
+        let readings: Vec<u32> = vec![0b10, 0b11, 0b00, 0b01, 0b01];
+        let trie = Trie::new(2, &readings, BitOrder::MostSignificantFirst).unwrap();
+
+        let rating = trie.co2_scrubber(TiePolicy::Ambiguous).unwrap();
+        let filtered = filter(
+            2,
+            readings,
+            &LeastCommon {
+                policy: TiePolicy::Ambiguous,
+                order: BitOrder::MostSignificantFirst,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(rating.ties, filtered.ties);
+        assert_eq!(rating.value, 0b10);
+        assert_eq!(filtered.value, 0b10);
+    }
+
+    #[test]
+    fn remove_readings() {
+        let mut trie = Trie::new(5, &example(), BitOrder::MostSignificantFirst).unwrap();
+
+        assert!(trie.remove(&23));
+        assert!(!trie.remove(&23));
+        assert!(!trie.remove(&31));
+        assert_eq!(trie.len(), 11);
+
+        let remaining: Vec<u32> = example().into_iter().filter(|&value| value != 23).collect();
+        assert_eq!(
+            trie.oxygen_generator(TiePolicy::PreferOne),
+            filter(5, remaining, &MostCommon::default())
+        );
+    }
+
+    #[test]
+    fn order_mismatch_and_empty() {
+        let trie = Trie::new(5, &example(), BitOrder::LeastSignificantFirst).unwrap();
+        assert_eq!(
+            trie.query(&MostCommon::default()),
+            Err(ReportError::OrderMismatch {
+                expected: BitOrder::LeastSignificantFirst,
+                found: BitOrder::MostSignificantFirst,
+            })
+        );
+
+        let trie = Trie::<u32>::new(5, &[], BitOrder::MostSignificantFirst).unwrap();
+        assert_eq!(
+            trie.oxygen_generator(TiePolicy::PreferOne),
+            Err(ReportError::Empty)
+        );
+
+        assert_eq!(
+            Trie::<u32>::new(40, &[1], BitOrder::MostSignificantFirst).err(),
+            Some(ReportError::TooWide {
+                bits: 40,
+                capacity: 32
+            })
+        );
+    }
+}
@@ -1,137 +1,109 @@
-use std::{fs::File, io::Read};
-
-fn parse_input<'a>(input: &'a str) -> (usize, impl Iterator<Item = u16> + 'a) {
-    let mut line_iter = input
-        .lines()
-        .filter_map(|line| {
-            let line = line.trim();
-            if line.len() > 0 {
-                Some(line)
-            } else {
-                None
-            }
-        })
-        .peekable();
-
-    let bits = line_iter.peek().unwrap().len();
-
-    (bits, line_iter.map(|x| u16::from_str_radix(x, 2).unwrap()))
-}
-
-fn calculate_oxygen_generator(bits: usize, diagnostic_input: Vec<u16>) -> u16 {
-    let mut data = diagnostic_input;
-
-    for i in (0..bits).rev() {
-        if data.len() == 1 {
-            return data[0];
+use day03_part1::{
+    cli::Options,
+    encoding::{decode, Encoding, InputOrder},
+    filter::{filter_traced, LeastCommon, MostCommon},
+    masked::{format_masked, Unknowns},
+    *,
+};
+
+fn print_ratings<W: Word>(
+    contents: &[u8],
+    options: &Options,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Options {
+        encoding, order, ..
+    } = *options;
+    let trace = options.flag("--trace");
+    let policy = TiePolicy::PreferOne;
+    if encoding == Encoding::Binary && contents.contains(&(masked::UNKNOWN as u8)) {
+        if trace {
+            return Err("traces need every bit of every reading to be known".into());
         }
-        let bit_selection = 1 << i;
-
-        let bits = data.iter().filter(|&x| x & bit_selection != 0).count();
-        let len = data.len();
-
-        let bit_mask = if bits * 2 >= len { bit_selection } else { 0 };
-        data = data
-            .into_iter()
-            .filter(|&value| (value ^ bit_mask) & bit_selection == 0)
-            .collect();
+        return print_masked_ratings::<W>(contents, order, policy);
     }
-
-    data[0]
-}
-
-fn calculate_co2_scrubber(bits: usize, diagnostic_input: Vec<u16>) -> u16 {
-    let mut data = diagnostic_input;
-
-    for i in (0..bits).rev() {
-        if data.len() == 1 {
-            return data[0];
-        }
-        let bit_selection = 1 << i;
-
-        let bits = data.iter().filter(|&x| x & bit_selection != 0).count();
-        let len = data.len();
-
-        let bit_mask = if bits * 2 < len { bit_selection } else { 0 };
-        data = data
-            .into_iter()
-            .filter(|&value| (value ^ bit_mask) & bit_selection == 0)
-            .collect();
+    let (bits, diagnostic_input) = decode::<W>(contents, encoding, order)?;
+
+    let (oxygen_generator, oxygen_trace) = filter_traced(
+        bits,
+        diagnostic_input.clone(),
+        &MostCommon {
+            policy,
+            ..MostCommon::default()
+        },
+    )?;
+    if trace {
+        print!("{}", oxygen_trace);
     }
-
-    data[0]
-}
-
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut contents = String::new();
-    {
-        let mut file = File::open("./day03/part1/input.txt")?;
-        file.read_to_string(&mut contents)?;
+    println!("Oxygen Generator: {}", oxygen_generator.value);
+
+    let (co2_scrubber, co2_trace) = filter_traced(
+        bits,
+        diagnostic_input,
+        &LeastCommon {
+            policy,
+            ..LeastCommon::default()
+        },
+    )?;
+    if trace {
+        print!("{}", co2_trace);
     }
-
-    let (bits, diagnostic_input) = parse_input(&contents);
-
-    let diagnostic_input: Vec<_> = diagnostic_input.collect();
-
-    let oxygen_generator = calculate_oxygen_generator(bits, diagnostic_input.clone());
-    println!("Oxygen Generator: {}", oxygen_generator);
-
-    let co2_scrubber = calculate_co2_scrubber(bits, diagnostic_input);
-    println!("CO2 Scrubber: {}", co2_scrubber);
+    println!("CO2 Scrubber: {}", co2_scrubber.value);
 
     println!(
         "Solution: {}",
-        oxygen_generator as u32 * co2_scrubber as u32
+        oxygen_generator.value.wide_mul(&co2_scrubber.value)
+    );
+    println!(
+        "Tied bits: oxygen {:?}, CO2 {:?}",
+        oxygen_generator.ties, co2_scrubber.ties
     );
 
     Ok(())
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
-
-    #[test]
-    fn example_parse() {
-        let input = "
-            00100
-            11110
-            10110
-            10111
-            10101
-            01111
-            00111
-            11100
-            10000
-            11001
-            00010
-            01010
-        ";
-
-        let (bits, iter) = parse_input(&input);
-
-        assert_eq!(bits, 5);
-        assert_eq!(
-            iter.collect::<Vec<_>>(),
-            vec![4, 30, 22, 23, 21, 15, 7, 28, 16, 25, 2, 10],
-        );
-    }
-
-    #[test]
-    fn example_calculate_oxygen_generator() {
-        let input = vec![4, 30, 22, 23, 21, 15, 7, 28, 16, 25, 2, 10];
+/// Readings with unknown bits keep the unknowns through each round, so a rating may come out
+/// with unknown bits of its own, and then there is no solution to print.
+fn print_masked_ratings<W: Word>(
+    contents: &[u8],
+    order: InputOrder,
+    policy: TiePolicy,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (bits, diagnostic_input) =
+        masked::parse_masked::<W>(&String::from_utf8_lossy(contents), order)?;
+    let oxygen_generator =
+        masked::calculate_oxygen_generator(bits, diagnostic_input.clone(), policy, Unknowns::Keep)?;
+    let co2_scrubber =
+        masked::calculate_co2_scrubber(bits, diagnostic_input, policy, Unknowns::Keep)?;
 
-        let oxygen_generator = calculate_oxygen_generator(5, input);
-
-        assert_eq!(oxygen_generator, 23);
+    println!(
+        "Oxygen Generator: {}",
+        format_masked(&oxygen_generator.value, bits)
+    );
+    println!("CO2 Scrubber: {}", format_masked(&co2_scrubber.value, bits));
+    if let (Some(oxygen_generator), Some(co2_scrubber)) = (
+        oxygen_generator.value.known_value(bits),
+        co2_scrubber.value.known_value(bits),
+    ) {
+        println!("Solution: {}", oxygen_generator.wide_mul(co2_scrubber));
     }
+    println!(
+        "Tied bits: oxygen {:?}, CO2 {:?}",
+        oxygen_generator.ties, co2_scrubber.ties
+    );
 
-    #[test]
-    fn example_calculate_co2_scrubber() {
-        let input = vec![4, 30, 22, 23, 21, 15, 7, 28, 16, 25, 2, 10];
-
-        let co2_scrubber = calculate_co2_scrubber(5, input);
+    Ok(())
+}
 
-        assert_eq!(co2_scrubber, 10);
-    }
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let options = Options::parse(std::env::args().skip(1), &["--trace"])?;
+    let path = options
+        .paths
+        .last()
+        .map_or("./day03/part1/input.txt", String::as_str);
+    let contents = std::fs::read(path)?;
+
+    with_word!(
+        Width::detect(options.encoding.width(&contents)?),
+        print_ratings(&contents, &options)
+    )
 }
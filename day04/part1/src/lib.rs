@@ -12,7 +12,7 @@ pub fn parse_input<'a>(
         .lines()
         .filter_map(|line| {
             let line = line.trim();
-            if line.len() > 0 {
+            if !line.is_empty() {
                 Some(line)
             } else {
                 None
@@ -27,19 +27,17 @@ pub fn parse_input<'a>(
         .map(|number| number.parse().unwrap());
 
     let boards = std::iter::from_fn(move || {
-        if let Some(_) = lines.peek() {
+        if lines.peek().is_some() {
             let mut board: Board = [[0; 5]; 5];
 
-            for row in 0..5 {
+            for row in board.iter_mut() {
                 let row_numbers: Vec<_> = lines
                     .next()
                     .unwrap()
                     .split_whitespace()
                     .map(|number| number.parse().unwrap())
                     .collect();
-                for column in 0..5 {
-                    board[row][column] = row_numbers[column];
-                }
+                row.copy_from_slice(&row_numbers[..5]);
             }
 
             Some(board)
@@ -115,13 +113,13 @@ pub fn get_board_score(
     winning_number: u8,
 ) -> u16 {
     let unmarked_sum: u16 = board
-        .into_iter()
-        .flat_map(|row| row.into_iter())
+        .iter()
+        .flat_map(|row| row.iter())
         .filter(|value| *numbers_called.get(value).unwrap_or(&usize::MAX) > winning_index)
         .map(|&number| number as u16)
         .sum();
 
-    return unmarked_sum as u16 * winning_number as u16;
+    unmarked_sum * winning_number as u16
 }
 
 #[cfg(test)]
@@ -152,7 +150,7 @@ mod test {
              2  0 12  3  7
         ";
 
-        let (called_numbers, board_iterator) = parse_input(&input);
+        let (called_numbers, board_iterator) = parse_input(input);
 
         assert_eq!(
             called_numbers.collect::<Vec<_>>(),